@@ -1,18 +1,79 @@
 // 移除未使用的导入
 use pitch_detection::detector::mcleod::McLeodDetector;
 use pitch_detection::detector::PitchDetector;
-use realfft::RealFftPlanner;
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::f32;
+use std::sync::Arc;
 use wasm_bindgen::prelude::*;
 use web_sys::{console, AudioBuffer};
 
+// 静音判定的电平下限（dBFS），低于此电平的帧视为静音
+const SILENCE_FLOOR_DB: f32 = -50.0;
+
+// 自动静音阈值相对于测得噪声底的余量（dB）
+const AUTO_SILENCE_MARGIN_DB: f32 = 10.0;
+
+// 过载指示灯的保持时间（毫秒），保证短暂削波在界面上也能看清
+const OVERLOAD_HOLD_MS: f32 = 500.0;
+
+// 压缩器 auto_release 的双时间常数（秒）：快包络的释放时间，慢包络的攻击和释放时间
+const AUTO_RELEASE_FAST_SEC: f32 = 0.05;
+const AUTO_RELEASE_SLOW_ATTACK_SEC: f32 = 0.5;
+const AUTO_RELEASE_SLOW_SEC: f32 = 1.0;
+
+// rms_history 的时间分辨率（毫秒）：每累计这么多输入样本记录一个RMS值，与每次调用的帧长无关
+const RMS_HISTORY_INTERVAL_MS: f32 = 50.0;
+
+// dereverberate 的晚期混响衰减模型：假定的混响时间（秒）和晚期混响相对直达声的起始延迟（毫秒）
+const DEREVERB_RT60_SEC: f32 = 0.5;
+const DEREVERB_DELAY_MS: f32 = 50.0;
+
+// generate_waveform 的取值模式
+const WAVEFORM_MODE_PEAK: u8 = 0;
+const WAVEFORM_MODE_RMS: u8 = 1;
+const WAVEFORM_MODE_BOTH: u8 = 2;
+
+// analyze_audio 的特征选择位
+const FEATURE_RMS: u32 = 1;
+const FEATURE_PEAK: u32 = 2;
+const FEATURE_PITCH: u32 = 4;
+const FEATURE_CENTROID: u32 = 8;
+const FEATURE_ZCR: u32 = 16;
+const FEATURE_ALL: u32 = FEATURE_RMS | FEATURE_PEAK | FEATURE_PITCH | FEATURE_CENTROID | FEATURE_ZCR;
+
+// analyze_spectrum_data / analyze_spectrum_magnitudes 的FFT长度
+const SPECTRUM_FFT_SIZE: usize = 1024;
+
 // 初始化 panic hook
 fn init_panic_hook() {
     // 直接调用，无需条件编译
     console_error_panic_hook::set_once();
 }
 
+// 幅度转换为dB，避免对零取对数
+fn amplitude_to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.max(1e-10).log10()
+}
+
+// dB转换为线性幅度
+fn db_to_amplitude(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+// Hz 转换为归一化频率（周期/样本，0.5 为奈奎斯特频率）；所有滤波器系数公式都经由此函数换算，避免单位混用
+fn hz_to_normalized<T: std::ops::Div<Output = T>>(hz: T, sample_rate: T) -> T {
+    hz / sample_rate
+}
+
+// 归一化频率（周期/样本）转换回 Hz
+fn normalized_to_hz<T: std::ops::Mul<Output = T>>(normalized: T, sample_rate: T) -> T {
+    normalized * sample_rate
+}
+
 // 音频特征结构
 #[derive(Serialize, Deserialize)]
 pub struct AudioFeatures {
@@ -24,7 +85,7 @@ pub struct AudioFeatures {
 }
 
 // 均衡器设置
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
 pub struct EqualizerSettings {
     pub bass: f32,
     pub mid: f32,
@@ -32,13 +93,22 @@ pub struct EqualizerSettings {
 }
 
 // 压缩器设置
+// oversample 只作用于压缩器本身：本库没有软削波/饱和等其他非线性环节，其余处理均不读取该字段
 #[derive(Serialize, Deserialize)]
 pub struct CompressorSettings {
-    pub threshold: f32,
+    pub threshold: f32, // 阈值（dBFS，如 -20.0），与检测到的电平包络按dB比较
     pub ratio: f32,
     pub attack: f32,
     pub release: f32,
     pub makeup_gain: f32,
+    #[serde(default)]
+    pub detector: u8, // 电平检测模式：0 = 峰值，1 = RMS（10ms滑动窗口）
+    #[serde(default)]
+    pub auto_makeup: bool, // 为 true 时忽略 makeup_gain，自动补偿增益使输出响度与输入一致
+    #[serde(default)]
+    pub auto_release: bool, // 为 true 时忽略 release，按节目内容自动调整释放时间（见 compress_gain）
+    #[serde(default)]
+    pub oversample: u8, // 过采样倍数：1、2 或 4，减少快速增益变化产生的混叠；缺省或其他值按1（不过采样）处理
 }
 
 // 频谱分析结果
@@ -51,13 +121,400 @@ pub struct SpectrumAnalysisResult {
     pub spectral_flux: f32,
 }
 
+// 只含幅度谱的频谱分析结果，用于只需绘制幅度的实时显示
+#[derive(Serialize, Deserialize)]
+pub struct SpectrumMagnitudes {
+    pub magnitudes: Vec<f32>,
+    pub dominant_frequency: f32,
+    pub spectral_flux: f32,
+}
+
 // 实时处理状态
 #[derive(Serialize, Deserialize)]
 pub struct RealTimeProcessorState {
     pub envelope: f32,
     pub pitch_history: Vec<f32>,
     pub spectral_flux_history: Vec<f32>,
-    pub rms_history: Vec<f32>,
+    pub rms_history: Vec<f32>, // 每 RMS_HISTORY_INTERVAL_MS（50ms）输入一个值，最新的在末尾
+    // 过载指示：帧内任一样本达到 ±1.0 时点亮，并保持 OVERLOAD_HOLD_MS 毫秒
+    pub overload: bool,
+    // 最近一帧的音高对应的音名和音分偏差（按 set_reference_pitch 设置的A4），该帧无音高时为 null
+    pub note: Option<NoteInfo>,
+}
+
+// 频率对应的最近音符
+#[derive(Serialize, Deserialize)]
+pub struct NoteInfo {
+    pub name: String, // 音名加八度，如 "A4"、"C#3"（升号表示）
+    pub midi: i32,    // MIDI音符编号，A4 = 69
+    pub cents: f32,   // 相对该音符的偏差（音分，-50 ~ +50）
+}
+
+// 增益包络断点
+#[derive(Serialize, Deserialize)]
+pub struct GainBreakpoint {
+    pub time_sec: f32,
+    pub gain: f32,
+}
+
+// 频谱门限的单个频段
+#[derive(Serialize, Deserialize)]
+pub struct GateBand {
+    pub low_hz: f32,
+    pub high_hz: f32,
+    pub threshold: f32,
+}
+
+// 频谱门限配置
+#[derive(Serialize, Deserialize)]
+pub struct SpectralGateProfile {
+    pub default_threshold: f32,      // 未指定频段使用的默认阈值
+    #[serde(default)]
+    pub bands: Vec<GateBand>,        // 各频段的阈值（重叠时取第一个匹配的频段）
+    #[serde(default)]
+    pub attenuation: f32,            // 低于阈值的频点保留的比例（0 表示完全置零）
+}
+
+// 频谱变化（spectral flux）的计算方式
+// Rectified：只累加幅度增长的部分（半波整流），适合检测起音（onset）
+// L2：幅度差的欧氏范数，增长和衰减都计入，适合检测整体的频谱变化（如段落切换）
+// Hfc：按频点序号加权的半波整流和，强调高频成分，适合检测辅音、打击乐等尖锐起音
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FluxMode {
+    Rectified = 0,
+    L2 = 1,
+    Hfc = 2,
+}
+
+// A/B 对比使用的处理预设（未提供的处理环节跳过）
+#[derive(Serialize, Deserialize)]
+pub struct ProcessingPreset {
+    #[serde(default)]
+    pub equalizer: Option<EqualizerSettings>,
+    #[serde(default)]
+    pub compressor: Option<CompressorSettings>,
+}
+
+// 响度匹配后的 A/B 对比结果
+#[derive(Serialize, Deserialize)]
+pub struct AbComparisonResult {
+    pub output_a: Vec<f32>,
+    pub output_b: Vec<f32>,
+    pub gain_a_db: f32,       // 为匹配响度对A施加的增益
+    pub gain_b_db: f32,       // 为匹配响度对B施加的增益
+    pub loudness_lufs: f32,   // 匹配后的共同响度
+}
+
+// 逐帧亮度特征（频谱质心、滚降频率单位为Hz）
+#[derive(Serialize, Deserialize)]
+pub struct BrightnessSeries {
+    pub times: Vec<f32>,
+    pub centroid: Vec<f32>,
+    pub rolloff: Vec<f32>,
+    pub crest: Vec<f32>,
+}
+
+// 窗函数类型
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WindowType {
+    Rectangular = 0,
+    Hann = 1,
+    Hamming = 2,
+    // 4项 Blackman-Harris：旁瓣约 -92dB，泄漏极低，但主瓣较宽，适合精确测量窄带成分
+    BlackmanHarris = 3,
+}
+
+impl WindowType {
+    // 生成周期窗（分母为N而非N-1），满足STFT重叠相加的COLA条件
+    fn coefficients(self, size: usize) -> Vec<f32> {
+        let phase = |i: usize| 2.0 * std::f32::consts::PI * i as f32 / size as f32;
+        
+        (0..size)
+            .map(|i| match self {
+                WindowType::Rectangular => 1.0,
+                WindowType::Hann => 0.5 - 0.5 * phase(i).cos(),
+                WindowType::Hamming => 0.54 - 0.46 * phase(i).cos(),
+                WindowType::BlackmanHarris => {
+                    0.35875 - 0.48829 * phase(i).cos() + 0.14128 * (2.0 * phase(i)).cos()
+                        - 0.01168 * (3.0 * phase(i)).cos()
+                }
+            })
+            .collect()
+    }
+}
+
+// 元音参考表中的一项（共振峰单位为Hz）
+#[derive(Serialize, Deserialize, Clone)]
+pub struct VowelReference {
+    pub vowel: String,
+    pub f1: f32,
+    pub f2: f32,
+}
+
+// 元音识别结果，清音或静音时各项为空
+#[derive(Serialize, Deserialize)]
+pub struct VowelClassification {
+    pub vowel: Option<String>,
+    pub f1: Option<f32>,
+    pub f2: Option<f32>,
+    pub distance: Option<f32>, // 与最近参考元音在Bark尺度上的距离
+}
+
+// 默认元音参考表：Peterson & Barney (1952) 美式英语成年男性平均共振峰
+fn default_vowel_table() -> Vec<VowelReference> {
+    [
+        ("i", 270.0, 2290.0),
+        ("ɪ", 390.0, 1990.0),
+        ("ɛ", 530.0, 1840.0),
+        ("æ", 660.0, 1720.0),
+        ("ɑ", 730.0, 1090.0),
+        ("ɔ", 570.0, 840.0),
+        ("ʊ", 440.0, 1020.0),
+        ("u", 300.0, 870.0),
+        ("ʌ", 640.0, 1190.0),
+        ("ɝ", 490.0, 1350.0),
+    ]
+    .iter()
+    .map(|&(vowel, f1, f2)| VowelReference {
+        vowel: vowel.to_string(),
+        f1,
+        f2,
+    })
+    .collect()
+}
+
+// 中/侧声道（M = (L + R) / 2，S = (L - R) / 2）
+#[derive(Serialize, Deserialize)]
+pub struct MidSideChannels {
+    pub mid: Vec<f32>,
+    pub side: Vec<f32>,
+}
+
+// 左/右声道
+#[derive(Serialize, Deserialize)]
+pub struct StereoChannels {
+    pub left: Vec<f32>,
+    pub right: Vec<f32>,
+}
+
+// 逐帧特征的帧数和时间信息
+#[derive(Serialize, Deserialize)]
+pub struct FrameTiming {
+    pub num_frames: usize,
+    pub hop_samples: usize,
+    pub window_samples: usize,
+    pub frame_times_sec: Vec<f32>, // 每帧起点时间（秒）
+}
+
+// 构建信息，用于确认现场加载的是哪个WASM构建
+#[derive(Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub crate_version: String,
+    pub features_enabled: Vec<String>,
+    pub simd: bool, // 是否以 simd128 目标特性编译
+}
+
+// 持续的窄带背景音（如投影仪啸叫）
+#[derive(Serialize, Deserialize)]
+pub struct TonalNoisePeak {
+    pub frequency: f32,   // 频率（Hz）
+    pub strength_db: f32, // 平均频谱中高出周围频谱基线的dB数
+    pub persistence: f32, // 出现该峰的帧所占比例（0~1）
+}
+
+// 逐帧语音活动特征（短时能量单位为dBFS）
+#[derive(Serialize, Deserialize)]
+pub struct SpeechActivitySeries {
+    pub times: Vec<f32>,
+    pub energy_db: Vec<f32>,
+    pub zcr: Vec<f32>,
+}
+
+// 错误码：数值保持稳定，JS端可按错误码分支处理或显示本地化文案
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ErrorCode {
+    TooShort = 1,         // 音频太短
+    BadSampleRate = 2,    // 采样率无效
+    FftFailed = 3,        // FFT/IFFT 处理失败
+    InvalidParameter = 4, // 参数超出有效范围
+    InvalidSettings = 5,  // 设置对象无法解析
+    LengthMismatch = 6,   // 多个输入缓冲区长度不一致
+    AnalysisFailed = 7,   // 分析无法得出结果
+}
+
+// 结构化错误，序列化为 { code, message } 返回给JS（message 为中文说明）
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProcessorError {
+    pub code: u32,
+    pub message: String,
+}
+
+impl ProcessorError {
+    fn new(code: ErrorCode, message: &str) -> Self {
+        ProcessorError {
+            code: code as u32,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<ProcessorError> for JsValue {
+    fn from(error: ProcessorError) -> Self {
+        serde_wasm_bindgen::to_value(&error).unwrap_or_else(|_| JsValue::from_str(&error.message))
+    }
+}
+
+// 解析JS传入的设置对象，失败时返回 InvalidSettings 错误
+fn parse_settings<T: DeserializeOwned>(value: JsValue) -> Result<T, JsValue> {
+    serde_wasm_bindgen::from_value(value)
+        .map_err(|error| ProcessorError::new(ErrorCode::InvalidSettings, &format!("设置格式无效：{}", error)).into())
+}
+
+// 语音片段（样本序号，左闭右开 [start, end)）
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub start: usize,
+    pub end: usize,
+}
+
+// 课程音频预处理结果
+#[derive(Serialize, Deserialize)]
+pub struct ClipPreprocessResult {
+    pub waveform: Vec<f32>,
+    pub features: AudioFeatures,
+    pub segments: Vec<Segment>,
+}
+
+// 响度随时间变化的曲线（LUFS）
+#[derive(Serialize, Deserialize)]
+pub struct LoudnessSeries {
+    pub times: Vec<f32>,
+    pub lufs: Vec<f32>,
+}
+
+// 压缩器增益缩减统计（单位dB，0 表示没有衰减）
+#[derive(Serialize, Deserialize, Default)]
+pub struct GainReductionStats {
+    pub max_gain_reduction_db: f32,
+    pub average_gain_reduction_db: f32, // 对所有样本取平均
+}
+
+// normalize_volume 的结果：所用线性增益、输出峰值（线性振幅），以及是否有样本超出 ±1.0
+#[derive(Serialize, Deserialize)]
+pub struct NormalizeResult {
+    pub gain: f32,
+    pub clipped: bool,
+    pub new_peak: f32,
+}
+
+// 逐窗口的语音/音乐分类结果
+#[derive(Serialize, Deserialize)]
+pub struct SpeechMusicSeries {
+    pub times: Vec<f32>,      // 窗口起点（秒）
+    pub labels: Vec<u8>,      // 0 = 音乐，1 = 语音，2 = 静音
+    pub confidence: Vec<f32>, // 对所给标签的置信度（0~1）
+}
+
+// 线性相位均衡的各频段增益（dB），缺省的频段为0dB
+#[derive(Serialize, Deserialize, Default)]
+pub struct EqBandGainsDb {
+    #[serde(default)]
+    pub bass: f32,
+    #[serde(default)]
+    pub mid: f32,
+    #[serde(default)]
+    pub treble: f32,
+}
+
+// 两段录音逐窗口的局部对齐结果
+#[derive(Serialize, Deserialize)]
+pub struct AlignmentMap {
+    pub times: Vec<f32>,      // 窗口起点（秒，以参考录音为时间轴）
+    pub lag_sec: Vec<f32>,    // 跟读录音相对参考的延迟（秒），正值表示跟读滞后
+    pub confidence: Vec<f32>, // 归一化互相关峰值（0~1），低能量窗口为0
+}
+
+// 跟读发音评分报告，各项得分均为 0~100
+#[derive(Serialize, Deserialize)]
+pub struct PronunciationReport {
+    pub overall_score: f32,
+    pub spectral_similarity: f32,     // DTW对齐后的频谱（音色/发音）相似度
+    pub pitch_similarity: f32,        // 语调（音高曲线）相似度
+    pub rhythm_similarity: f32,       // 节奏（对齐路径偏离匀速的程度）相似度
+    pub duration_ratio: f32,          // 跟读有效时长 / 参考有效时长（原始比值，不是得分）
+    pub alignment_path: Vec<[f32; 2]>, // DTW对齐路径，每个点为 [参考时间, 跟读时间]（秒）
+}
+
+// 发音评分用的逐帧特征，只覆盖首个到最后一个非静音帧
+struct UtteranceFeatures {
+    start_sec: f32,
+    bands: Vec<Vec<f32>>,     // 每帧各Bark频带的对数能量（dB），已减去各频带的均值
+    pitch: Vec<Option<f32>>,  // 浊音帧的音高（相对A4的半音数）
+}
+
+// 音高候选：频率（Hz）及其NSDF峰值（清晰度，最大为1）
+#[derive(Serialize, Deserialize)]
+pub struct PitchCandidate {
+    pub frequency: f32,
+    pub clarity: f32,
+}
+
+// 录音质量检查的判定阈值，未指定的字段使用默认值
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingQualityThresholds {
+    pub max_dc_offset: f32,      // 直流偏移（样本均值的绝对值）超过此值判为有直流偏移，默认0.01
+    pub clip_level: f32,         // 绝对值达到此值的样本计为削波，默认0.999
+    pub max_clipping_ratio: f32, // 削波样本比例超过此值判为削波，默认0.001（0.1%）
+    pub max_silence_ratio: f32,  // 静音帧比例超过此值判为大部分是静音，默认0.8
+    pub min_snr_db: f32,         // 估计信噪比低于此值判为噪声过大/声音过小，默认15dB
+    pub min_duration_sec: f32,   // 时长短于此值判为过短，默认1秒
+}
+
+impl Default for RecordingQualityThresholds {
+    fn default() -> Self {
+        Self {
+            max_dc_offset: 0.01,
+            clip_level: 0.999,
+            max_clipping_ratio: 0.001,
+            max_silence_ratio: 0.8,
+            min_snr_db: 15.0,
+            min_duration_sec: 1.0,
+        }
+    }
+}
+
+// 录音质量检查结果
+#[derive(Serialize, Deserialize)]
+pub struct RecordingQualityReport {
+    pub dc_offset: f32,
+    pub has_dc_offset: bool,
+    pub clipping_ratio: f32, // 削波样本占全部样本的比例
+    pub is_clipped: bool,
+    pub silence_ratio: f32,  // 20ms帧中低于静音阈值的比例
+    pub mostly_silent: bool,
+    pub estimated_snr_db: f32,
+    pub low_snr: bool,
+    pub too_short: bool,
+}
+
+// 自动推荐的处理设置，附带测量值和中文理由说明
+#[derive(Serialize, Deserialize)]
+pub struct ProcessingSuggestion {
+    pub normalize_gain_db: f32,                          // 建议增益（dB），0 表示无需调整
+    pub suggested_hpf_hz: f32,                           // 建议的高通截止频率（Hz），0 表示不需要高通
+    pub needs_denoise: bool,
+    pub suggested_compressor: Option<CompressorSettings>, // 不需要压缩时为 null
+    pub loudness_lufs: Option<f32>,                      // 积分响度，无法测量（如太短或静音）时为 null
+    pub peak_dbfs: f32,
+    pub noise_floor_db: f32,
+    pub estimated_snr_db: f32,
+    pub rumble_ratio: f32,                               // 80Hz以下能量占总能量的比例
+    pub crest_factor_db: f32,                            // 峰值与门限RMS电平之差
+    pub reasons: Vec<String>,                            // 每条建议的理由
 }
 
 // AudioProcessor 主处理器类
@@ -69,7 +526,42 @@ pub struct AudioProcessor {
     pitch_history: Vec<f32>,
     spectral_flux_history: Vec<f32>,
     rms_history: Vec<f32>,
+    rms_accumulator: (f64, usize), // 尚未凑满一个 rms_history 间隔的样本平方和及样本数
     prev_spectrum: Option<Vec<f32>>,
+    flux_mode: FluxMode,
+    low_mid_crossover: f32,
+    mid_high_crossover: f32,
+    vowel_table: Vec<VowelReference>,
+    overload_hold_remaining: usize,
+    eq_settings: EqualizerSettings,
+    auto_silence_threshold: bool,
+    envelope_smoothing: EnvelopeSmoothing,
+    eq_block_filters: Option<(IIRFilter, IIRFilter)>,
+    eq_band_enabled: [bool; 3],
+    pitch_window: PitchWindow,
+    reference_a4_hz: f32,
+    latest_pitch: Option<f32>,
+    denoise_gain_smoothing: f32,
+}
+
+// process_audio_frame 中电平包络的平滑方式
+#[derive(Clone, Copy)]
+enum EnvelopeSmoothing {
+    // 每帧固定系数：envelope = c * envelope + (1 - c) * rms
+    Fixed(f32),
+    // 由上升/下降时间常数（毫秒）按帧长换算系数，与调用频率和帧大小无关
+    Time { attack_ms: f32, release_ms: f32 },
+}
+
+// detect_pitch 在较长音频中选取1024点分析窗口的方式
+#[derive(Clone, Copy)]
+enum PitchWindow {
+    // 音频正中间（旧行为）
+    Center,
+    // 能量最大的窗口，短单词的浊音段不在中间时也能检测到
+    Loudest,
+    // 从指定样本偏移开始，超出范围时截断到最后一个完整窗口
+    Offset(usize),
 }
 
 #[wasm_bindgen]
@@ -86,50 +578,171 @@ impl AudioProcessor {
             pitch_history: vec![0.0; 10],
             spectral_flux_history: vec![0.0; 30],
             rms_history: vec![0.0; 30],
+            rms_accumulator: (0.0, 0),
             prev_spectrum: None,
+            flux_mode: FluxMode::Rectified,
+            low_mid_crossover: 200.0,
+            mid_high_crossover: 4000.0,
+            vowel_table: default_vowel_table(),
+            overload_hold_remaining: 0,
+            eq_settings: EqualizerSettings { bass: 1.0, mid: 1.0, treble: 1.0 },
+            auto_silence_threshold: false,
+            envelope_smoothing: EnvelopeSmoothing::Fixed(0.9),
+            pitch_window: PitchWindow::Loudest,
+            reference_a4_hz: 440.0,
+            latest_pitch: None,
+            denoise_gain_smoothing: 0.0,
+            eq_block_filters: None,
+            eq_band_enabled: [true; 3],
         }
     }
     
-    // 设置采样率
+    // 设置采样率（必须大于0）
     #[wasm_bindgen]
-    pub fn set_sample_rate(&mut self, sample_rate: usize) {
+    pub fn set_sample_rate(&mut self, sample_rate: usize) -> Result<(), JsValue> {
+        if sample_rate == 0 {
+            return Err(ProcessorError::new(ErrorCode::BadSampleRate, "采样率必须大于0").into());
+        }
+        
         self.sample_rate = sample_rate;
+        // 滤波器系数依赖采样率，下次分块处理时重建
+        self.eq_block_filters = None;
+        
+        Ok(())
+    }
+    
+    // 设置三段均衡器的分频点（单位Hz，默认 200Hz / 4000Hz），中频为两个分频点之间的部分
+    // 要求 0 < low_mid_hz < mid_high_hz < 奈奎斯特频率
+    #[wasm_bindgen]
+    pub fn set_crossover(&mut self, low_mid_hz: f32, mid_high_hz: f32) -> Result<(), JsValue> {
+        let nyquist = self.sample_rate as f32 / 2.0;
+        if !(low_mid_hz > 0.0 && low_mid_hz < mid_high_hz && mid_high_hz < nyquist) {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "分频点必须满足 0 < 低/中分频点 < 中/高分频点 < 奈奎斯特频率").into());
+        }
+        
+        self.low_mid_crossover = low_mid_hz;
+        self.mid_high_crossover = mid_high_hz;
+        self.eq_block_filters = None;
+        
+        Ok(())
+    }
+    
+    // 清空实时分析状态（包络、音高/频谱变化/RMS历史、上一帧频谱和过载保持），
+    // 恢复为构造时的默认值；切换曲目时调用，避免上一段音频的数据混入新的可视化
+    // 采样率、分频点、均衡设置等配置保持不变
+    #[wasm_bindgen]
+    pub fn reset_analysis(&mut self) {
+        self.envelope = 0.0;
+        self.pitch_history = vec![0.0; 10];
+        self.spectral_flux_history = vec![0.0; 30];
+        self.rms_history = vec![0.0; 30];
+        self.rms_accumulator = (0.0, 0);
+        self.prev_spectrum = None;
+        self.overload_hold_remaining = 0;
+        self.latest_pitch = None;
+    }
+    
+    // 设置音名换算使用的标准音A4频率（Hz，默认440），影响 frequency_to_note 和实时状态中的 note
+    #[wasm_bindgen]
+    pub fn set_reference_pitch(&mut self, a4_hz: f32) -> Result<(), JsValue> {
+        if !(a4_hz > 0.0 && a4_hz.is_finite()) {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "标准音频率必须为正数").into());
+        }
+        
+        self.reference_a4_hz = a4_hz;
+        Ok(())
+    }
+    
+    // 把频率（Hz）换算为最近的音符 { name, midi, cents }（按当前的A4标准音），频率无效时返回 null
+    #[wasm_bindgen]
+    pub fn frequency_to_note(&self, freq_hz: f32) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.note_info(freq_hz))?)
+    }
+    
+    fn note_info(&self, freq_hz: f32) -> Option<NoteInfo> {
+        const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+        if !(freq_hz > 0.0 && freq_hz.is_finite()) {
+            return None;
+        }
+        
+        let midi_float = 69.0 + 12.0 * (freq_hz / self.reference_a4_hz).log2();
+        let midi = midi_float.round() as i32;
+        let octave = midi.div_euclid(12) - 1;
+        
+        Some(NoteInfo {
+            name: format!("{}{}", NOTE_NAMES[midi.rem_euclid(12) as usize], octave),
+            midi,
+            cents: (midi_float - midi as f32) * 100.0,
+        })
+    }
+    
+    // 设置频谱变化的计算方式（默认为半波整流和）
+    #[wasm_bindgen]
+    pub fn set_flux_mode(&mut self, mode: FluxMode) {
+        self.flux_mode = mode;
+    }
+    
+    // 设置静音判定方式：false 使用固定的 -50dBFS（默认），
+    // true 按每段输入测得的噪声底 + 10dB 自动设定（要求素材中含有一些停顿）
+    // 影响 classify_voicing、detect_whisper、analyze_brightness_series 和 classify_vowel
+    #[wasm_bindgen]
+    pub fn set_auto_silence_threshold(&mut self, enabled: bool) {
+        self.auto_silence_threshold = enabled;
     }
     
     // 生成波形数据
+    // mode 选择每个波形点的取值方式（省略时为峰值，与原行为一致）：
+    // 0 = 峰值：区间内最大绝对振幅，适合瞬态丰富的素材
+    // 1 = RMS：区间内均方根，更接近感知响度
+    // 2 = 两者交错：[峰值0, RMS0, 峰值1, RMS1, ...]，长度为 2 * num_points
+    // 两种取值都以满刻度为1.0的线性振幅表示，不做额外归一化，因此峰值和RMS可以直接比较
+    // 数据少于 num_points 时按位置取最近样本的绝对值（此时峰值与RMS相同）
+    // num_points 为0时返回空数组
+    // signed 为 true 时（省略时为 false）峰值保留符号：取区间内绝对值最大的样本原值，范围 [-1, 1]，
+    // 可显示真实的波形形状（放大查看时更准确）；此时绘制和归一化应以0为中心对称处理，RMS 仍为非负值
     #[wasm_bindgen]
-    pub fn generate_waveform(&self, audio_data: &[f32], num_points: u32) -> Box<[f32]> {
+    pub fn generate_waveform(&self, audio_data: &[f32], num_points: u32, mode: Option<u8>, signed: Option<bool>) -> Box<[f32]> {
         let num_points = num_points as usize;
-        let mut result = vec![0.0; num_points];
+        let mode = mode.unwrap_or(WAVEFORM_MODE_PEAK);
+        let signed = signed.unwrap_or(false);
+        let values_per_point = if mode == WAVEFORM_MODE_BOTH { 2 } else { 1 };
+        let mut result = vec![0.0; num_points * values_per_point];
         
-        if audio_data.is_empty() {
+        // 无数据时返回全零结果，num_points 为0时为空数组（避免除以0）
+        if audio_data.is_empty() || num_points == 0 {
             return result.into_boxed_slice();
         }
         
         // 每个波形点代表的样本数
         let samples_per_point = audio_data.len() / num_points;
-        if samples_per_point < 1 {
-            // 数据点太少，需要插值
-            for i in 0..num_points {
+        
+        for (i, point) in result.chunks_exact_mut(values_per_point).enumerate() {
+            let (peak, rms) = if samples_per_point < 1 {
+                // 数据点太少，需要插值
                 let idx = (i as f32 * audio_data.len() as f32 / num_points as f32) as usize;
-                result[i] = audio_data[std::cmp::min(idx, audio_data.len() - 1)].abs();
-            }
-        } else {
-            // 对每个点找出代表区间的最大振幅
-            for i in 0..num_points {
+                let sample = audio_data[std::cmp::min(idx, audio_data.len() - 1)];
+                (if signed { sample } else { sample.abs() }, sample.abs())
+            } else {
+                // 计算代表区间的最大振幅和均方根
                 let start = i * samples_per_point;
-                let end = (i + 1) * samples_per_point;
-                let end = std::cmp::min(end, audio_data.len());
+                let end = std::cmp::min((i + 1) * samples_per_point, audio_data.len());
+                let segment = &audio_data[start..end];
                 
-                let mut max_amp = 0.0;
-                for j in start..end {
-                    let amp = audio_data[j].abs();
-                    if amp > max_amp {
-                        max_amp = amp;
-                    }
+                let peak = if signed {
+                    segment.iter().copied().fold(0.0f32, |peak, sample| if sample.abs() > peak.abs() { sample } else { peak })
+                } else {
+                    self.calculate_peak(segment)
+                };
+                (peak, self.calculate_rms(segment))
+            };
+            
+            match mode {
+                WAVEFORM_MODE_RMS => point[0] = rms,
+                WAVEFORM_MODE_BOTH => {
+                    point[0] = peak;
+                    point[1] = rms;
                 }
-                
-                result[i] = max_amp;
+                _ => point[0] = peak,
             }
         }
         
@@ -137,213 +750,436 @@ impl AudioProcessor {
     }
     
     // 应用均衡器
+    // settings 为 { bass, mid, treble } 线性增益倍数（1.0 为不变）；分频点由 set_crossover 以Hz设置
+    // 三段增益均为1时输出与输入相同。与早期版本不同：中频不再经过峰值滤波器，输出也不再固定除以3，
+    // 因此同样的设置输出约高 9.5dB，中频形状也有变化
     #[wasm_bindgen]
-    pub fn apply_equalizer(&self, audio_data: &mut [f32], settings: JsValue) -> Result<(), JsValue> {
-        let settings: EqualizerSettings = serde_wasm_bindgen::from_value(settings.clone())?;
+    pub fn apply_equalizer(&mut self, audio_data: &mut [f32], settings: JsValue) -> Result<(), JsValue> {
+        let settings: EqualizerSettings = parse_settings(settings)?;
+        
+        // 记录当前均衡设置，供 compute_eq_response 绘制曲线
+        self.eq_settings = settings;
+        self.apply_equalizer_internal(audio_data, &settings);
+        
+        Ok(())
+    }
+    
+    // 均衡器处理的内部实现
+    fn apply_equalizer_internal(&self, audio_data: &mut [f32], settings: &EqualizerSettings) {
+        let settings = &self.effective_eq_settings(settings);
         
         // 仅当有需要时才处理
         if (settings.bass - 1.0).abs() < 0.01 && 
            (settings.mid - 1.0).abs() < 0.01 && 
            (settings.treble - 1.0).abs() < 0.01 {
-            return Ok(());
+            return;
         }
         
         // 创建三段式均衡器
-        let mut bass_filter = IIRFilter::low_pass(200.0 / self.sample_rate as f32, 0.707);
-        let mut mid_filter = IIRFilter::peak(1000.0 / self.sample_rate as f32, 1.0, 
-                                          (settings.mid - 1.0) * 12.0); // 将线性增益转换为dB增益
-        let mut treble_filter = IIRFilter::high_pass(4000.0 / self.sample_rate as f32, 0.707);
+        let (mut bass_filter, mut treble_filter) = self.equalizer_filters();
         
-        // 计算增益系数
+        // 计算增益系数（线性倍数，1.0 为不变）
         let bass_gain = settings.bass;
+        let mid_gain = settings.mid;
         let treble_gain = settings.treble;
         
-        // 处理音频
-        let mut filtered_audio = vec![0.0; audio_data.len()];
-        
-        // 低频处理
-        for i in 0..audio_data.len() {
-            let bass = bass_filter.process(audio_data[i]) * bass_gain;
-            filtered_audio[i] += bass;
-        }
-        
-        // 中频处理 - 直接通过峰值滤波器
-        for i in 0..audio_data.len() {
-            filtered_audio[i] += mid_filter.process(audio_data[i]);
+        // 输出 = 原信号 * 中频增益 + 低频 * (低频增益 - 中频增益) + 高频 * (高频增益 - 中频增益)
+        // 三段增益均为1时输出与输入完全相同
+        // 两个滤波器各自只依赖输入样本，因此逐样本一次完成并就地写回，无需额外的整段缓冲区
+        for sample in audio_data.iter_mut() {
+            let input = *sample;
+            *sample = input * mid_gain
+                + bass_filter.process(input) * (bass_gain - mid_gain)
+                + treble_filter.process(input) * (treble_gain - mid_gain);
         }
+    }
+    
+    // 以dB为单位应用三段均衡：0dB 为不变，+6dB 约为该频段幅度加倍，-6dB 约为减半
+    // 内部按 10^(dB/20) 转换为 apply_equalizer 使用的线性倍数
+    #[wasm_bindgen]
+    pub fn apply_equalizer_db(&mut self, audio_data: &mut [f32], bass_db: f32, mid_db: f32, treble_db: f32) {
+        let settings = EqualizerSettings {
+            bass: db_to_amplitude(bass_db),
+            mid: db_to_amplitude(mid_db),
+            treble: db_to_amplitude(treble_db),
+        };
         
-        // 高频处理
-        for i in 0..audio_data.len() {
-            let treble = treble_filter.process(audio_data[i]) * treble_gain;
-            filtered_audio[i] += treble;
-        }
+        self.eq_settings = settings;
+        self.apply_equalizer_internal(audio_data, &settings);
+    }
+    
+    // 离线高精度三段均衡：与 apply_equalizer 相同的设置和算法，但滤波器状态和运算均为f64，
+    // 适合长时间离线渲染（f32滤波器状态在数分钟的级联处理中会累积误差）；实时路径仍使用f32
+    #[wasm_bindgen]
+    pub fn apply_equalizer_f64(&mut self, audio_data: &mut [f64], settings: JsValue) -> Result<(), JsValue> {
+        let settings: EqualizerSettings = parse_settings(settings)?;
         
-        // 将处理后的音频写回原始缓冲区
-        for i in 0..audio_data.len() {
-            audio_data[i] = filtered_audio[i] / 3.0; // 均衡三段信号电平
-        }
+        self.eq_settings = settings;
+        self.apply_equalizer_f64_internal(audio_data, &settings);
         
         Ok(())
     }
     
-    // 应用音频压缩
-    #[wasm_bindgen]
-    pub fn apply_compression(&self, audio_data: &mut [f32], settings: JsValue) -> Result<(), JsValue> {
-        let settings: CompressorSettings = serde_wasm_bindgen::from_value(settings)?;
+    fn apply_equalizer_f64_internal(&self, audio_data: &mut [f64], settings: &EqualizerSettings) {
+        let settings = &self.effective_eq_settings(settings);
+        
+        // 仅当有需要时才处理
+        if (settings.bass - 1.0).abs() < 0.01 && 
+           (settings.mid - 1.0).abs() < 0.01 && 
+           (settings.treble - 1.0).abs() < 0.01 {
+            return;
+        }
         
-        // 压缩时间常数
-        let attack_coef = (-1.0 / (settings.attack * self.sample_rate as f32)).exp();
-        let release_coef = (-1.0 / (settings.release * self.sample_rate as f32)).exp();
+        let sample_rate = self.sample_rate as f64;
+        let (low_mid, mid_high) = self.equalizer_crossovers();
+        let mut bass_filter = BiquadFilterF64::low_pass(low_mid as f64, sample_rate, 0.707);
+        let mut treble_filter = BiquadFilterF64::high_pass(mid_high as f64, sample_rate, 0.707);
         
-        // 压缩状态
-        let mut envelope = 0.0;
+        let bass_gain = settings.bass as f64;
+        let mid_gain = settings.mid as f64;
+        let treble_gain = settings.treble as f64;
         
-        // 处理每个样本
+        // 输出 = 原信号 * 中频增益 + 低频 * (低频增益 - 中频增益) + 高频 * (高频增益 - 中频增益)
         for sample in audio_data.iter_mut() {
-            // 计算当前样本电平
-            let input_level = sample.abs();
-            
-            // 包络跟踪
-            if input_level > envelope {
-                envelope = attack_coef * (envelope - input_level) + input_level; // 攻击
-            } else {
-                envelope = release_coef * (envelope - input_level) + input_level; // 释放
-            }
-            
-            // 计算增益缩减 (dB)
-            let mut gain_reduction = 0.0;
-            if envelope > settings.threshold {
-                // 如果超过阈值，计算增益缩减（单位：dB）
-                let slope = 1.0 - 1.0 / settings.ratio;
-                gain_reduction = slope * (envelope.log10() * 20.0 - settings.threshold);
-            }
-            
-            // 将增益缩减从dB转换为线性
-            let gain = 10.0f32.powf(-gain_reduction / 20.0) * 10.0f32.powf(settings.makeup_gain / 20.0);
-            
-            // 应用增益
-            *sample *= gain;
+            let input = *sample;
+            *sample = input * mid_gain
+                + bass_filter.process(input) * (bass_gain - mid_gain)
+                + treble_filter.process(input) * (treble_gain - mid_gain);
         }
-        
-        Ok(())
     }
     
-    // 音频降噪
+    // 设置分块均衡的三段增益（线性倍数，1.0 为不变），供 process_eq_block 使用
+    // 三段增益只参与输出求和，不影响滤波器系数，因此调整增益不会重新计算系数
     #[wasm_bindgen]
-    pub fn denoise_audio(&self, audio_data: &mut [f32], noise_threshold: f32) -> Result<(), JsValue> {
-        // 移除未使用变量警告
-        let _sample_rate = self.sample_rate;
-        let fft_size = 2048; // FFT大小
-        let hop_size = fft_size / 4; // 帧移
-        
-        // 检查音频长度是否足够
-        if audio_data.len() < fft_size {
-            return Err(JsValue::from_str("音频太短，无法进行降噪处理"));
+    pub fn set_eq_gains(&mut self, bass: f32, mid: f32, treble: f32) {
+        self.eq_settings = EqualizerSettings { bass, mid, treble };
+    }
+    
+    // 用 set_eq_gains 设置的增益对一个音频块做三段均衡（流式处理）
+    // 滤波器及其历史状态在各次调用之间保留，块与块之间连续无缝；
+    // 系数只在首次调用或采样率/分频点改变后重新计算
+    #[wasm_bindgen]
+    pub fn process_eq_block(&mut self, block: &mut [f32]) {
+        if self.eq_block_filters.is_none() {
+            self.eq_block_filters = Some(self.equalizer_filters());
         }
+        let settings = self.effective_eq_settings(&self.eq_settings);
+        let Some((bass_filter, treble_filter)) = self.eq_block_filters.as_mut() else {
+            return;
+        };
         
-        // 创建FFT计划
-        let mut planner = RealFftPlanner::<f32>::new();
-        let r2c = planner.plan_fft_forward(fft_size);
-        let c2r = planner.plan_fft_inverse(fft_size);
-        
-        // 分配FFT缓冲区
-        let mut buffer = vec![0.0; fft_size];
-        let mut spectrum = r2c.make_output_vec();
-        let mut output_buffer = vec![0.0; fft_size];
-        
-        // 汉宁窗函数
-        let window: Vec<f32> = (0..fft_size)
-            .map(|i| 0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / (fft_size as f32 - 1.0)).cos()))
-            .collect();
-        
-        // 处理每一帧
-        let mut processed_audio = vec![0.0; audio_data.len()];
-        for i in (0..audio_data.len().saturating_sub(fft_size)).step_by(hop_size) {
-            // 复制并加窗
-            for j in 0..fft_size {
-                if i + j < audio_data.len() {
-                    buffer[j] = audio_data[i + j] * window[j];
-                } else {
-                    buffer[j] = 0.0;
-                }
+        // 输出 = 原信号 * 中频增益 + 低频 * (低频增益 - 中频增益) + 高频 * (高频增益 - 中频增益)
+        for sample in block.iter_mut() {
+            let input = *sample;
+            *sample = input * settings.mid
+                + bass_filter.process(input) * (settings.bass - settings.mid)
+                + treble_filter.process(input) * (settings.treble - settings.mid);
+        }
+    }
+    
+    // 启用或旁通均衡器的某个频段（0 = 低频，1 = 中频，2 = 高频），便于逐段对比每个频段对语音的影响
+    // 旁通的频段按增益1处理，即原样通过；三段全部旁通时均衡器完全透明。其他 band 值被忽略
+    // 对 apply_equalizer / apply_equalizer_db / apply_equalizer_f64 / process_eq_block 和 compute_eq_response 均生效
+    #[wasm_bindgen]
+    pub fn set_band_enabled(&mut self, band: u8, enabled: bool) {
+        if let Some(flag) = self.eq_band_enabled.get_mut(band as usize) {
+            *flag = enabled;
+        }
+    }
+    
+    // 把被旁通频段的增益替换为1后的实际均衡设置
+    fn effective_eq_settings(&self, settings: &EqualizerSettings) -> EqualizerSettings {
+        let [bass_enabled, mid_enabled, treble_enabled] = self.eq_band_enabled;
+        EqualizerSettings {
+            bass: if bass_enabled { settings.bass } else { 1.0 },
+            mid: if mid_enabled { settings.mid } else { 1.0 },
+            treble: if treble_enabled { settings.treble } else { 1.0 },
+        }
+    }
+    
+    // 三段均衡的分频点（限制在奈奎斯特频率以下，防止采样率改变后越界）
+    fn equalizer_crossovers(&self) -> (f32, f32) {
+        let max_freq = self.sample_rate as f32 * 0.49;
+        (self.low_mid_crossover.min(max_freq), self.mid_high_crossover.min(max_freq))
+    }
+    
+    // 三段均衡使用的低通/高通滤波器
+    fn equalizer_filters(&self) -> (IIRFilter, IIRFilter) {
+        let sample_rate = self.sample_rate as f32;
+        let (low_mid, mid_high) = self.equalizer_crossovers();
+        
+        (
+            IIRFilter::low_pass(low_mid, sample_rate, 0.707),
+            IIRFilter::high_pass(mid_high, sample_rate, 0.707),
+        )
+    }
+    
+    // 计算当前均衡器（最近一次 apply_equalizer / apply_equalizer_db / set_eq_gains 的设置和当前分频点）
+    // 在给定频率（freqs_hz，单位Hz）处的幅度响应（dB），用于界面绘制EQ曲线，无需处理音频
+    // 与处理时相同：H = 中频增益 + 低通 * (低频增益 - 中频增益) + 高通 * (高频增益 - 中频增益)
+    #[wasm_bindgen]
+    pub fn compute_eq_response(&self, freqs_hz: &[f32]) -> Box<[f32]> {
+        let (bass_filter, treble_filter) = self.equalizer_filters();
+        let sample_rate = self.sample_rate as f32;
+        let settings = &self.effective_eq_settings(&self.eq_settings);
+        
+        freqs_hz
+            .iter()
+            .map(|&freq| {
+                let response = Complex::new(settings.mid, 0.0)
+                    + bass_filter.response(freq, sample_rate) * (settings.bass - settings.mid)
+                    + treble_filter.response(freq, sample_rate) * (settings.treble - settings.mid);
+                amplitude_to_db(response.norm())
+            })
+            .collect::<Vec<f32>>()
+            .into_boxed_slice()
+    }
+    
+    // 应用音频压缩
+    // 返回增益缩减统计 { max_gain_reduction_db, average_gain_reduction_db }，用于界面上的增益缩减表
+    #[wasm_bindgen]
+    pub fn apply_compression(&self, audio_data: &mut [f32], settings: JsValue) -> Result<JsValue, JsValue> {
+        let settings: CompressorSettings = parse_settings(settings)?;
+        
+        let stats = self.apply_compression_internal(audio_data, &settings);
+        
+        Ok(serde_wasm_bindgen::to_value(&stats)?)
+    }
+    
+    // 压缩处理的内部实现
+    fn apply_compression_internal(&self, audio_data: &mut [f32], settings: &CompressorSettings) -> GainReductionStats {
+        self.compress(audio_data, None, settings)
+    }
+    
+    // 侧链压缩：包络跟随 sidechain（如人声），增益作用于 audio_data（如背景音乐），用于旁白时自动压低音乐
+    // 两个缓冲区长度必须一致；设置和返回的增益缩减统计与 apply_compression 相同
+    #[wasm_bindgen]
+    pub fn apply_compression_sidechain(&self, audio_data: &mut [f32], sidechain: &[f32], settings: JsValue) -> Result<JsValue, JsValue> {
+        if audio_data.len() != sidechain.len() {
+            return Err(ProcessorError::new(ErrorCode::LengthMismatch, "侧链信号与处理信号长度不一致").into());
+        }
+        
+        let settings: CompressorSettings = parse_settings(settings)?;
+        let stats = self.compress(audio_data, Some(sidechain), &settings);
+        
+        Ok(serde_wasm_bindgen::to_value(&stats)?)
+    }
+    
+    // 压缩核心：sidechain 为 None 时由 audio_data 自身驱动包络；返回增益缩减统计（不含补偿增益）
+    // oversample 为2或4时先用sinc插值升采样，在高采样率下计算包络和增益，再经抗混叠低通降回原采样率，
+    // 使攻击时间很短时逐样本增益变化产生的高次谐波不会折叠回可听频段
+    fn compress(&self, audio_data: &mut [f32], sidechain: Option<&[f32]>, settings: &CompressorSettings) -> GainReductionStats {
+        let factor = match settings.oversample {
+            2 => 2,
+            4 => 4,
+            _ => 1,
+        };
+        
+        // 自动补偿时先测量输入响度，压缩后再统一补偿
+        let input_level = if settings.auto_makeup { self.measured_level_db(audio_data) } else { None };
+        let makeup_gain = if settings.auto_makeup { 0.0 } else { settings.makeup_gain };
+        
+        let stats = if factor == 1 {
+            self.compress_gain(audio_data, sidechain, settings, self.sample_rate as f32, makeup_gain)
+        } else {
+            let len = audio_data.len();
+            let step = 1.0 / factor as f64;
+            let mut upsampled = sinc_resample(audio_data, step, len * factor);
+            let upsampled_sidechain = sidechain.map(|detector| sinc_resample(detector, step, len * factor));
+            let stats = self.compress_gain(
+                &mut upsampled,
+                upsampled_sidechain.as_deref(),
+                settings,
+                (self.sample_rate * factor) as f32,
+                makeup_gain,
+            );
+            audio_data.copy_from_slice(&sinc_resample(&upsampled, factor as f64, len));
+            stats
+        };
+        
+        if let (Some(input_db), Some(output_db)) = (input_level, self.measured_level_db(audio_data)) {
+            let gain = db_to_amplitude(input_db - output_db);
+            audio_data.iter_mut().for_each(|s| *s *= gain);
+        }
+        
+        stats
+    }
+    
+    // 按给定采样率逐样本计算并施加压缩增益（含固定补偿增益 makeup_gain，单位dB）
+    fn compress_gain(
+        &self,
+        audio_data: &mut [f32],
+        sidechain: Option<&[f32]>,
+        settings: &CompressorSettings,
+        sample_rate: f32,
+        makeup_gain: f32,
+    ) -> GainReductionStats {
+        // 包络跟踪（攻击/释放时间常数和峰值/RMS检测）
+        // auto_release 时使用双时间常数：快包络（释放50ms）跟踪瞬态，慢包络（攻击500ms、释放1s）只在持续的大音量段才充起来，
+        // 取两者较大值。短暂峰值过后慢包络很低，增益按快包络迅速恢复；持续大声之后慢包络主导，释放自然变长
+        let release = if settings.auto_release { AUTO_RELEASE_FAST_SEC } else { settings.release };
+        let mut follower = EnvelopeFollower::new(sample_rate, settings.attack, release, settings.detector);
+        let mut slow_follower = settings.auto_release.then(|| {
+            EnvelopeFollower::new(sample_rate, AUTO_RELEASE_SLOW_ATTACK_SEC, AUTO_RELEASE_SLOW_SEC, settings.detector)
+        });
+        
+        let mut stats = GainReductionStats::default();
+        let mut reduction_sum = 0.0f64;
+        
+        // 处理每个样本
+        for (i, sample) in audio_data.iter_mut().enumerate() {
+            let detector_input = sidechain.map_or(*sample, |detector| detector[i]);
+            let mut envelope = follower.process(detector_input);
+            if let Some(slow) = slow_follower.as_mut() {
+                envelope = envelope.max(slow.process(detector_input));
+            }
+            
+            // 计算增益缩减 (dB)，阈值以dB为单位
+            let mut gain_reduction = 0.0;
+            let envelope_db = amplitude_to_db(envelope);
+            if envelope_db > settings.threshold {
+                // 如果超过阈值，计算增益缩减（单位：dB）
+                let slope = 1.0 - 1.0 / settings.ratio;
+                gain_reduction = slope * (envelope_db - settings.threshold);
             }
+            stats.max_gain_reduction_db = stats.max_gain_reduction_db.max(gain_reduction);
+            reduction_sum += gain_reduction as f64;
+            
+            // 将增益缩减从dB转换为线性
+            let gain = 10.0f32.powf(-gain_reduction / 20.0) * 10.0f32.powf(makeup_gain / 20.0);
             
-            // 执行FFT
-            r2c.process(&mut buffer, &mut spectrum).expect("FFT处理失败");
+            // 应用增益
+            *sample *= gain;
+        }
+        
+        if !audio_data.is_empty() {
+            stats.average_gain_reduction_db = (reduction_sum / audio_data.len() as f64) as f32;
+        }
+        stats
+    }
+    
+    // 测量用于增益匹配的电平：优先使用积分响度（LUFS），
+    // 不足一个400ms分块或未通过门限时退回RMS电平（dBFS）；完全静音时返回 None
+    fn measured_level_db(&self, audio_data: &[f32]) -> Option<f32> {
+        self.integrated_loudness(audio_data).or_else(|| {
+            let rms = self.calculate_rms(audio_data);
+            (rms > 0.0).then(|| amplitude_to_db(rms))
+        })
+    }
+    
+    // 音频降噪（频谱减法）
+    // passes 为处理遍数（缺省为1，即单遍处理）：每一遍都在上一遍的输出上重新计算频谱并做减法，
+    // 阈值逐遍减半（noise_threshold、noise_threshold/2、noise_threshold/4……）以限制“音乐噪声”。
+    // 多遍对很嘈杂的录音能进一步压低残余噪声，但收益递减，超过2~3遍后更容易损伤语音（辅音和弱音节被削掉）
+    // 逐帧增益的时间平滑由 set_denoise_smoothing 控制（默认不平滑）
+    #[wasm_bindgen]
+    pub fn denoise_audio(&self, audio_data: &mut [f32], noise_threshold: f32, passes: Option<u32>) -> Result<(), JsValue> {
+        let fft_size = 2048; // FFT大小
+        let hop_size = fft_size / 4; // 帧移
+        
+        // 检查音频长度是否足够
+        if audio_data.len() < fft_size {
+            return Err(ProcessorError::new(ErrorCode::TooShort, "音频太短，无法进行降噪处理").into());
+        }
+        
+        let mut threshold = noise_threshold;
+        for _ in 0..passes.unwrap_or(1).max(1) {
+            self.spectral_subtract(audio_data, fft_size, hop_size, threshold)?;
+            threshold *= 0.5;
+        }
+        
+        Ok(())
+    }
+    
+    // 单遍频谱减法：每个频点的幅度减去 noise_threshold（不低于0），相位不变
+    // 每个频点的增益（处理后幅度 / 原幅度）按 set_denoise_smoothing 的系数在相邻帧之间递归平均，系数为0时逐帧独立
+    fn spectral_subtract(&self, audio_data: &mut [f32], fft_size: usize, hop_size: usize, noise_threshold: f32) -> Result<(), JsValue> {
+        let smoothing = self.denoise_gain_smoothing;
+        let mut smoothed_gains: Vec<f32> = Vec::new();
+        
+        // 逐帧进行频谱减法降噪，重叠相加的归一化由 OverlapAdd 负责
+        process_stft(audio_data, fft_size, hop_size, |spectrum| {
+            let first_frame = smoothed_gains.is_empty();
+            if first_frame {
+                smoothed_gains.resize(spectrum.len(), 0.0);
+            }
             
-            // 频谱减法降噪
-            for k in 0..spectrum.len() {
+            for (bin, smoothed_gain) in spectrum.iter_mut().zip(smoothed_gains.iter_mut()) {
                 // 计算频谱幅度
-                let re = spectrum[k].re;
-                let im = spectrum[k].im;
-                let magnitude = (re * re + im * im).sqrt();
+                let magnitude = (bin.re * bin.re + bin.im * bin.im).sqrt();
                 
-                // 应用噪声阈值（频谱减法）
-                let new_magnitude = if magnitude > noise_threshold {
-                    magnitude - noise_threshold
+                // 应用噪声阈值（频谱减法）得到本帧增益
+                let gain = if magnitude > 1e-10 && magnitude > noise_threshold {
+                    (magnitude - noise_threshold) / magnitude
                 } else {
                     0.0
                 };
                 
-                // 重建频谱
-                if magnitude > 1e-10 {
-                    let scale = new_magnitude / magnitude;
-                    spectrum[k].re *= scale;
-                    spectrum[k].im *= scale;
+                // 增益的时间递归平均，首帧直接使用本帧增益
+                *smoothed_gain = if first_frame {
+                    gain
                 } else {
-                    spectrum[k].re = 0.0;
-                    spectrum[k].im = 0.0;
-                }
-            }
-            
-            // 执行IFFT
-            c2r.process(&mut spectrum, &mut output_buffer).expect("IFFT处理失败");
-            
-            // 叠加到输出
-            for j in 0..fft_size {
-                if i + j < processed_audio.len() {
-                    processed_audio[i + j] += output_buffer[j] * window[j] / (fft_size as f32 * 1.5);
-                }
+                    smoothing * *smoothed_gain + (1.0 - smoothing) * gain
+                };
+                
+                // 重建频谱
+                bin.re *= *smoothed_gain;
+                bin.im *= *smoothed_gain;
             }
-        }
-        
-        // 复制回原缓冲区
-        for i in 0..audio_data.len() {
-            audio_data[i] = processed_audio[i];
-        }
-        
-        Ok(())
+        })
+    }
+    
+    // 设置降噪增益的时间平滑系数（0~0.99，默认0）：每个频点的增益 = c * 上一帧增益 + (1 - c) * 本帧增益，
+    // 使抑制量随时间平缓变化，减少逐帧独立增益造成的“音乐噪声”和颤动感；过大会让语音起音被短暂压低
+    #[wasm_bindgen]
+    pub fn set_denoise_smoothing(&mut self, coefficient: f32) {
+        self.denoise_gain_smoothing = coefficient.clamp(0.0, 0.99);
     }
     
     // 音频特征提取
+    // flags 为按位或的特征选择（省略时计算全部，与原行为一致）：
+    // 1 = rms，2 = peak，4 = pitch（FFT/NSDF，开销大），8 = spectral_centroid（FFT，开销大），16 = zero_crossing_rate
+    // 未选择的特征不计算，数值字段为0，pitch 为 null；实时电平表只需 rms | peak（3）
+    // pitch 默认分析能量最大的窗口而非音频正中间（见 detect_pitch），结果可能与早期版本不同
     #[wasm_bindgen]
-    pub fn analyze_audio(&self, audio_data: &[f32]) -> Result<JsValue, JsValue> {
+    pub fn analyze_audio(&self, audio_data: &[f32], flags: Option<u32>) -> Result<JsValue, JsValue> {
+        let features = self.analyze_features(audio_data, flags.unwrap_or(FEATURE_ALL));
+        
+        // 转换为JS对象
+        Ok(serde_wasm_bindgen::to_value(&features)?)
+    }
+    
+    fn analyze_audio_internal(&self, audio_data: &[f32]) -> AudioFeatures {
+        self.analyze_features(audio_data, FEATURE_ALL)
+    }
+    
+    // 按 flags 只计算所选特征，未选择的为0（pitch 为 None）
+    fn analyze_features(&self, audio_data: &[f32], flags: u32) -> AudioFeatures {
+        let wants = |feature: u32| flags & feature != 0;
+        
         // 计算RMS
-        let rms = self.calculate_rms(audio_data);
+        let rms = if wants(FEATURE_RMS) { self.calculate_rms(audio_data) } else { 0.0 };
         
         // 计算峰值
-        let peak = self.calculate_peak(audio_data);
+        let peak = if wants(FEATURE_PEAK) { self.calculate_peak(audio_data) } else { 0.0 };
         
         // 计算基频（音高）
-        let pitch = self.detect_pitch(audio_data);
+        let pitch = if wants(FEATURE_PITCH) { self.detect_pitch(audio_data) } else { None };
         
         // 计算频谱质心
-        let spectral_centroid = self.calculate_spectral_centroid(audio_data);
+        let spectral_centroid = if wants(FEATURE_CENTROID) { self.calculate_spectral_centroid(audio_data) } else { 0.0 };
         
         // 计算过零率
-        let zero_crossing_rate = self.calculate_zero_crossing_rate(audio_data);
+        let zero_crossing_rate = if wants(FEATURE_ZCR) { self.calculate_zero_crossing_rate(audio_data) } else { 0.0 };
         
         // 创建特征结构
-        let features = AudioFeatures {
+        AudioFeatures {
             rms,
             peak,
             pitch,
             spectral_centroid,
             zero_crossing_rate,
-        };
-        
-        // 转换为JS对象
-        Ok(serde_wasm_bindgen::to_value(&features)?)
+        }
     }
     
     // 计算RMS (Root Mean Square) 均方根振幅
@@ -356,6 +1192,28 @@ impl AudioProcessor {
         (sum_squares / audio_data.len() as f32).sqrt()
     }
     
+    // 门限RMS：按50ms分帧，只对RMS电平高于 gate_db（dBFS）的帧求均方根，
+    // 避免长停顿拉低语音电平（类似广播响度测量的门限）；没有帧通过门限时返回0
+    #[wasm_bindgen]
+    pub fn calculate_rms_gated(&self, audio_data: &[f32], gate_db: f32) -> f32 {
+        let frame_size = self.ms_to_samples(50.0);
+        let mut sum_squares = 0.0f64;
+        let mut count = 0;
+        
+        for frame in audio_data.chunks(frame_size) {
+            if amplitude_to_db(self.calculate_rms(frame)) > gate_db {
+                sum_squares += frame.iter().map(|&x| (x * x) as f64).sum::<f64>();
+                count += frame.len();
+            }
+        }
+        
+        if count == 0 {
+            return 0.0;
+        }
+        
+        (sum_squares / count as f64).sqrt() as f32
+    }
+    
     // 计算峰值振幅
     fn calculate_peak(&self, audio_data: &[f32]) -> f32 {
         if audio_data.is_empty() {
@@ -366,6 +1224,8 @@ impl AudioProcessor {
     }
     
     // 检测基频（音高）
+    // 分析窗口由 pitch_window 选取，默认为能量最大的1024点窗口（早期版本固定取音频正中间）；
+    // 因此 analyze_audio / process_audio_frame 对同一输入的 pitch 可能与旧版本不同，需要旧行为时调用 set_pitch_window_center
     fn detect_pitch(&self, audio_data: &[f32]) -> Option<f32> {
         if audio_data.len() < 1024 {
             return None;
@@ -374,18 +1234,63 @@ impl AudioProcessor {
         // 使用McLeod音高检测算法
         let mut detector = McLeodDetector::new(1024, 512);
         
-        // 准备输入数据（使用数据的中段以避免边缘效应）
-        let start = audio_data.len().saturating_sub(1024) / 2;
+        // 准备输入数据（按 pitch_window 选取1024点窗口）
+        let start = self.pitch_window_start(audio_data, 1024);
         let buffer: Vec<f32> = audio_data.iter().skip(start).take(1024).cloned().collect();
         
         // 检测音高
-        let pitch_result = detector.get_pitch(&buffer, self.sample_rate as usize, 0.2, 0.7);
+        let pitch_result = detector.get_pitch(&buffer, self.sample_rate, 0.2, 0.7);
         
         // 返回检测结果
         pitch_result.map(|p| p.frequency)
     }
     
+    // 音高检测（analyze_audio）使用音频正中间的1024点窗口，即旧版行为
+    #[wasm_bindgen]
+    pub fn set_pitch_window_center(&mut self) {
+        self.pitch_window = PitchWindow::Center;
+    }
+    
+    // 音高检测使用能量最大的1024点窗口（默认），避免短单词的浊音段偏离中间时检测不到
+    #[wasm_bindgen]
+    pub fn set_pitch_window_loudest(&mut self) {
+        self.pitch_window = PitchWindow::Loudest;
+    }
+    
+    // 音高检测使用从 offset（样本）开始的1024点窗口，超出音频范围时截断到最后一个完整窗口
+    #[wasm_bindgen]
+    pub fn set_pitch_window_offset(&mut self, offset: u32) {
+        self.pitch_window = PitchWindow::Offset(offset as usize);
+    }
+    
+    // 按 pitch_window 计算长度为 window 的分析窗口起点，调用方保证 audio_data.len() >= window
+    fn pitch_window_start(&self, audio_data: &[f32], window: usize) -> usize {
+        let last_start = audio_data.len() - window;
+        match self.pitch_window {
+            PitchWindow::Center => last_start / 2,
+            PitchWindow::Offset(offset) => offset.min(last_start),
+            PitchWindow::Loudest => {
+                // 滑动窗口能量，逐样本更新
+                let mut energy: f64 = audio_data[..window].iter().map(|&x| (x * x) as f64).sum();
+                let mut best_energy = energy;
+                let mut best_start = 0;
+                for start in 1..=last_start {
+                    let removed = audio_data[start - 1] as f64;
+                    let added = audio_data[start + window - 1] as f64;
+                    energy += added * added - removed * removed;
+                    if energy > best_energy {
+                        best_energy = energy;
+                        best_start = start;
+                    }
+                }
+                best_start
+            }
+        }
+    }
+    
     // 计算频谱质心
+    // 注意：只分析缓冲区中间的1024个样本，结果为FFT频点序号而非Hz，对长录音意义有限；
+    // 保留用于 analyze_audio 的兼容，需要随时间变化的质心请使用 spectral_centroid_series
     fn calculate_spectral_centroid(&self, audio_data: &[f32]) -> f32 {
         if audio_data.len() < 1024 {
             return 0.0;
@@ -447,6 +1352,13 @@ impl AudioProcessor {
     // 实时处理一帧音频数据
     #[wasm_bindgen]
     pub fn process_audio_frame(&mut self, audio_frame: &mut [f32], settings: JsValue) -> Result<JsValue, JsValue> {
+        // 在处理前检测输入过载，命中后重新开始保持计时，否则按帧长递减
+        if self.check_overload(audio_frame) {
+            self.overload_hold_remaining = self.ms_to_samples(OVERLOAD_HOLD_MS);
+        } else {
+            self.overload_hold_remaining = self.overload_hold_remaining.saturating_sub(audio_frame.len());
+        }
+        
         // 克隆settings避免移动
         let settings_clone = settings.clone();
         
@@ -455,25 +1367,23 @@ impl AudioProcessor {
         
         // 更新包络跟踪器（用于音量监测）
         let current_rms = self.calculate_rms(audio_frame);
-        self.envelope = 0.9 * self.envelope + 0.1 * current_rms;
+        let smoothing = self.envelope_smoothing_coef(current_rms, audio_frame.len());
+        self.envelope = smoothing * self.envelope + (1.0 - smoothing) * current_rms;
         
-        // 更新RMS历史
-        self.rms_history.remove(0);
-        self.rms_history.push(current_rms);
+        // 更新RMS历史（按固定时间间隔，跨帧累计）
+        self.update_rms_history(audio_frame);
         
         // 检测音高并更新历史
-        if let Some(pitch) = self.detect_pitch(audio_frame) {
+        self.latest_pitch = self.detect_pitch(audio_frame);
+        if let Some(pitch) = self.latest_pitch {
             self.pitch_history.remove(0);
             self.pitch_history.push(pitch);
         }
         
-        // 计算频谱并更新频谱变化历史
-        if let Ok(spectrum_data) = self.analyze_spectrum_data(audio_frame) {
-            if let Ok(spectrum_result) = serde_wasm_bindgen::from_value::<SpectrumAnalysisResult>(spectrum_data) {
-                self.spectral_flux_history.remove(0);
-                self.spectral_flux_history.push(spectrum_result.spectral_flux);
-            }
-        }
+        // 计算频谱变化并更新历史（只跟踪幅度谱，不构建完整的频谱分析结果）
+        let spectral_flux = self.compute_spectral_flux(audio_frame);
+        self.spectral_flux_history.remove(0);
+        self.spectral_flux_history.push(spectral_flux);
         
         // 创建返回状态
         let state = RealTimeProcessorState {
@@ -481,21 +1391,127 @@ impl AudioProcessor {
             pitch_history: self.pitch_history.clone(),
             spectral_flux_history: self.spectral_flux_history.clone(),
             rms_history: self.rms_history.clone(),
+            overload: self.overload_hold_remaining > 0,
+            note: self.latest_pitch.and_then(|pitch| self.note_info(pitch)),
         };
         
         Ok(serde_wasm_bindgen::to_value(&state)?)
     }
     
+    // 按固定的 RMS_HISTORY_INTERVAL_MS 间隔累计样本，每凑满一个间隔就把该间隔的RMS推入 rms_history，
+    // 不足一个间隔的样本留到下一帧继续累计；因此历史的时间轴不受回调帧长变化影响，一帧也可能推入0个或多个值
+    fn update_rms_history(&mut self, audio_frame: &[f32]) {
+        let interval = self.ms_to_samples(RMS_HISTORY_INTERVAL_MS);
+        let (mut sum_squares, mut count) = self.rms_accumulator;
+        
+        for &sample in audio_frame {
+            sum_squares += (sample * sample) as f64;
+            count += 1;
+            
+            if count >= interval {
+                self.rms_history.remove(0);
+                self.rms_history.push((sum_squares / count as f64).sqrt() as f32);
+                sum_squares = 0.0;
+                count = 0;
+            }
+        }
+        
+        self.rms_accumulator = (sum_squares, count);
+    }
+    
+    // 设置电平包络的固定平滑系数（0~1，默认0.9）：越小响应越快（适合电平表），越大越平滑（适合氛围可视化）
+    #[wasm_bindgen]
+    pub fn set_envelope_smoothing(&mut self, coefficient: f32) {
+        self.envelope_smoothing = EnvelopeSmoothing::Fixed(coefficient.clamp(0.0, 0.999));
+    }
+    
+    // 按上升/下降时间（毫秒）设置电平包络的平滑：每帧系数由帧长换算，
+    // 因此不受帧大小和调用频率影响；时间为0时立即跟随
+    #[wasm_bindgen]
+    pub fn set_envelope_times(&mut self, attack_ms: f32, release_ms: f32) {
+        self.envelope_smoothing = EnvelopeSmoothing::Time {
+            attack_ms: attack_ms.max(0.0),
+            release_ms: release_ms.max(0.0),
+        };
+    }
+    
+    // 当前帧使用的包络平滑系数
+    fn envelope_smoothing_coef(&self, current_rms: f32, frame_len: usize) -> f32 {
+        match self.envelope_smoothing {
+            EnvelopeSmoothing::Fixed(coefficient) => coefficient,
+            EnvelopeSmoothing::Time { attack_ms, release_ms } => {
+                let time_ms = if current_rms > self.envelope { attack_ms } else { release_ms };
+                // 逐样本系数的帧长次幂
+                time_constant_coef(time_ms / 1000.0, self.sample_rate as f32).powi(frame_len as i32)
+            }
+        }
+    }
+    
+    // 检查帧内是否有样本达到满刻度（±1.0），不改变处理器状态
+    #[wasm_bindgen]
+    pub fn check_overload(&self, frame: &[f32]) -> bool {
+        frame.iter().any(|sample| sample.abs() >= 1.0)
+    }
+    
     // 频谱分析
     // 计算频谱分析结果，但不暴露给WebAssembly
     fn analyze_spectrum_internal(&mut self, audio_data: &[f32]) -> Option<SpectrumAnalysisResult> {
+        let (result, spectrum) = self.analyze_spectrum_magnitudes_internal(audio_data)?;
+        
+        // 计算相位和频率
+        let phases = spectrum.iter().map(|bin| bin.im.atan2(bin.re)).collect();
+        let frequencies = self.bin_frequencies(SPECTRUM_FFT_SIZE);
+        
+        Some(SpectrumAnalysisResult {
+            magnitudes: result.magnitudes,
+            phases,
+            frequencies,
+            dominant_frequency: result.dominant_frequency,
+            spectral_flux: result.spectral_flux,
+        })
+    }
+    
+    // 只计算幅度谱、主频率和频谱变化，同时返回复数频谱供需要相位的调用方使用
+    fn analyze_spectrum_magnitudes_internal(&mut self, audio_data: &[f32]) -> Option<(SpectrumMagnitudes, Vec<Complex<f32>>)> {
         if audio_data.len() < 512 {
             return None;
         }
         
-        let fft_size = 1024;
-        let sample_rate = self.sample_rate;
+        // 执行加窗FFT
+        let spectrum = self.windowed_spectrum(audio_data, SPECTRUM_FFT_SIZE)?;
+        
+        // 计算幅度
+        let mut magnitudes = vec![0.0; spectrum.len()];
+        let mut max_magnitude = 0.0;
+        let mut max_magnitude_idx = 0;
+        
+        for (i, bin) in spectrum.iter().enumerate() {
+            let magnitude = (bin.re * bin.re + bin.im * bin.im).sqrt();
+            magnitudes[i] = magnitude;
+            
+            if magnitude > max_magnitude {
+                max_magnitude = magnitude;
+                max_magnitude_idx = i;
+            }
+        }
+        
+        let dominant_frequency = normalized_to_hz(max_magnitude_idx as f32 / SPECTRUM_FFT_SIZE as f32, self.sample_rate as f32);
         
+        // 计算频谱变化（与上一帧相比）并更新先前频谱
+        let spectral_flux = self.update_spectral_flux(&magnitudes);
+        
+        Some((
+            SpectrumMagnitudes {
+                magnitudes,
+                dominant_frequency,
+                spectral_flux,
+            },
+            spectrum,
+        ))
+    }
+    
+    // 对最近 fft_size 个样本加汉宁窗后执行FFT（不足部分补零）
+    fn windowed_spectrum(&mut self, audio_data: &[f32], fft_size: usize) -> Option<Vec<Complex<f32>>> {
         // 提取分析窗口
         let mut analysis_window = Vec::with_capacity(fft_size);
         let start_idx = audio_data.len().saturating_sub(fft_size);
@@ -516,65 +1532,60 @@ impl AudioProcessor {
         let mut buffer = analysis_window;
         let mut spectrum = r2c.make_output_vec();
         
-        if let Err(_) = r2c.process(&mut buffer, &mut spectrum) {
+        if r2c.process(&mut buffer, &mut spectrum).is_err() {
             return None;
         }
         
-        // 计算幅度和相位
-        let mut magnitudes = vec![0.0; spectrum.len()];
-        let mut phases = vec![0.0; spectrum.len()];
-        let mut max_magnitude = 0.0;
-        let mut max_magnitude_idx = 0;
+        Some(spectrum)
+    }
+    
+    // 计算与上一帧相比的频谱变化，并更新先前频谱
+    fn update_spectral_flux(&mut self, magnitudes: &[f32]) -> f32 {
+        let spectral_flux = match &self.prev_spectrum {
+            Some(prev) => spectral_flux(prev, magnitudes, self.flux_mode),
+            None => 0.0,
+        };
         
-        for (i, bin) in spectrum.iter().enumerate() {
-            let magnitude = (bin.re * bin.re + bin.im * bin.im).sqrt();
-            let phase = bin.im.atan2(bin.re);
-            
-            magnitudes[i] = magnitude;
-            phases[i] = phase;
-            
-            if magnitude > max_magnitude {
-                max_magnitude = magnitude;
-                max_magnitude_idx = i;
-            }
+        // 更新先前频谱（长度不变时复用已有缓冲区）
+        match &mut self.prev_spectrum {
+            Some(prev) if prev.len() == magnitudes.len() => prev.copy_from_slice(magnitudes),
+            _ => self.prev_spectrum = Some(magnitudes.to_vec()),
         }
         
-        // 计算频率
-        let frequencies = (0..spectrum.len())
-            .map(|i| i as f32 * sample_rate as f32 / fft_size as f32)
-            .collect::<Vec<f32>>();
-        
-        let dominant_frequency = max_magnitude_idx as f32 * sample_rate as f32 / fft_size as f32;
+        spectral_flux
+    }
+    
+    // 只计算频谱变化的快速路径：仅跟踪幅度谱，不分配相位/频率数组也不做序列化
+    // 供实时处理使用，需要完整结果时仍使用 analyze_spectrum_data
+    fn compute_spectral_flux(&mut self, audio_data: &[f32]) -> f32 {
+        if audio_data.len() < 512 {
+            return 0.0;
+        }
         
-        // 计算频谱变化（与上一帧相比）
-        let spectral_flux = match &self.prev_spectrum {
-            Some(prev) => {
-                // 限制到最小长度
-                let min_len = std::cmp::min(prev.len(), magnitudes.len());
-                
-                // 计算频谱变化
-                let mut flux = 0.0;
-                for i in 0..min_len {
-                    let diff = magnitudes[i] - prev[i];
-                    // 只考虑增长的部分（HFC - High Frequency Content）
-                    flux += if diff > 0.0 { diff } else { 0.0 };
-                }
-                
-                flux
-            },
-            None => 0.0,
+        let spectrum = match self.windowed_spectrum(audio_data, SPECTRUM_FFT_SIZE) {
+            Some(spectrum) => spectrum,
+            None => return 0.0,
         };
         
-        // 更新先前频谱
-        self.prev_spectrum = Some(magnitudes.clone());
+        let magnitudes: Vec<f32> = spectrum.iter().map(|bin| (bin.re * bin.re + bin.im * bin.im).sqrt()).collect();
+        self.update_spectral_flux(&magnitudes)
+    }
+    
+    // 返回给定FFT大小下每个频点的中心频率（Hz），从0到奈奎斯特频率共 fft_size / 2 + 1 个，
+    // 与 analyze_spectrum_data 返回的 frequencies 一致；相邻频点间隔为 采样率 / fft_size
+    #[wasm_bindgen]
+    pub fn fft_bin_frequencies(&self, fft_size: u32) -> Box<[f32]> {
+        self.bin_frequencies(fft_size as usize).into_boxed_slice()
+    }
+    
+    fn bin_frequencies(&self, fft_size: usize) -> Vec<f32> {
+        if fft_size == 0 {
+            return Vec::new();
+        }
         
-        Some(SpectrumAnalysisResult {
-            magnitudes,
-            phases,
-            frequencies,
-            dominant_frequency,
-            spectral_flux,
-        })
+        (0..=fft_size / 2)
+            .map(|i| normalized_to_hz(i as f32 / fft_size as f32, self.sample_rate as f32))
+            .collect()
     }
     
     // 公开的WebAssembly接口，返回频谱分析结果
@@ -582,7 +1593,18 @@ impl AudioProcessor {
     pub fn analyze_spectrum_data(&mut self, audio_data: &[f32]) -> Result<JsValue, JsValue> {
         match self.analyze_spectrum_internal(audio_data) {
             Some(result) => Ok(serde_wasm_bindgen::to_value(&result)?),
-            None => Err(JsValue::from_str("无法分析频谱数据")),
+            None => Err(ProcessorError::new(ErrorCode::AnalysisFailed, "无法分析频谱数据").into()),
+        }
+    }
+    
+    // analyze_spectrum_data 的精简版本，只返回 { magnitudes, dominant_frequency, spectral_flux }，
+    // 省去 phases 和 frequencies，使实时频谱显示每帧跨越WASM边界的数据减少约三分之二；
+    // 频点频率可用 fft_bin_frequencies(1024) 一次性取得。频谱变化历史与 analyze_spectrum_data 共用
+    #[wasm_bindgen]
+    pub fn analyze_spectrum_magnitudes(&mut self, audio_data: &[f32]) -> Result<JsValue, JsValue> {
+        match self.analyze_spectrum_magnitudes_internal(audio_data) {
+            Some((result, _)) => Ok(serde_wasm_bindgen::to_value(&result)?),
+            None => Err(ProcessorError::new(ErrorCode::AnalysisFailed, "无法分析频谱数据").into()),
         }
     }
     
@@ -594,53 +1616,3637 @@ impl AudioProcessor {
             pitch_history: self.pitch_history.clone(),
             spectral_flux_history: self.spectral_flux_history.clone(),
             rms_history: self.rms_history.clone(),
+            overload: self.overload_hold_remaining > 0,
+            note: self.latest_pitch.and_then(|pitch| self.note_info(pitch)),
         };
         
         Ok(serde_wasm_bindgen::to_value(&state)?)
     }
-}
-
-// 工具函数：从 AudioBuffer 提取单声道数据
-#[wasm_bindgen]
-pub fn extract_mono_from_buffer(buffer: &AudioBuffer) -> Box<[f32]> {
-    let length = buffer.get_channel_data(0).unwrap().len() as usize;
-    let mut mono_data = vec![0.0; length];
-    
-    // 获取第一个通道数据
-    let channel_data = buffer.get_channel_data(0).unwrap();
-    
-    // 复制数据
-    for i in 0..length {
-        mono_data[i] = channel_data[i];
-    }
     
-    // 如果有多个通道，计算平均值
-    let num_channels = buffer.number_of_channels();
-    if num_channels > 1 {
-        for channel in 1..num_channels {
-            let channel_data = buffer.get_channel_data(channel as u32).unwrap();
-            for i in 0..length {
-                mono_data[i] += channel_data[i];
-            }
+    // 应用随时间变化的增益包络（如旁白下压低背景音乐）
+    // 断点之间线性插值，第一个断点之前和最后一个断点之后保持端点增益
+    #[wasm_bindgen]
+    pub fn apply_gain_envelope(&self, audio_data: &mut [f32], breakpoints: JsValue) -> Result<(), JsValue> {
+        let mut breakpoints: Vec<GainBreakpoint> = parse_settings(breakpoints)?;
+        
+        if breakpoints.is_empty() {
+            return Ok(());
+        }
+        
+        // 按时间排序断点
+        breakpoints.sort_by(|a, b| a.time_sec.partial_cmp(&b.time_sec).unwrap_or(std::cmp::Ordering::Equal));
+        
+        let sample_rate = self.sample_rate as f32;
+        let mut segment = 0;
+        
+        for (i, sample) in audio_data.iter_mut().enumerate() {
+            let time = i as f32 / sample_rate;
+            
+            // 前进到当前时间所在的断点区间
+            while segment + 1 < breakpoints.len() && time >= breakpoints[segment + 1].time_sec {
+                segment += 1;
+            }
+            
+            let current = &breakpoints[segment];
+            let gain = if time <= current.time_sec || segment + 1 >= breakpoints.len() {
+                // 第一个断点之前或最后一个断点之后，保持端点增益
+                current.gain
+            } else {
+                // 两个断点之间线性插值
+                let next = &breakpoints[segment + 1];
+                let fraction = (time - current.time_sec) / (next.time_sec - current.time_sec);
+                current.gain + (next.gain - current.gain) * fraction
+            };
+            
+            *sample *= gain;
+        }
+        
+        Ok(())
+    }
+    
+    // 去除咔哒声/爆音（适用于黑胶或老旧录音）
+    // 通过二阶差分的局部离群值检测突变样本，并在受损样本上线性插值
+    // sensitivity 取值 0~1，越大越敏感；阈值相对于周围约 2ms 内的平均二阶差分，
+    // 且只修复不超过 1ms 的短区域，避免把真实语音中的爆破音当作咔哒声
+    #[wasm_bindgen]
+    pub fn remove_clicks(&self, audio_data: &mut [f32], sensitivity: f32) {
+        let len = audio_data.len();
+        if len < 5 {
+            return;
+        }
+        
+        let sample_rate = self.sample_rate as f32;
+        let sensitivity = sensitivity.clamp(0.0, 1.0);
+        let threshold_factor = 40.0 - 30.0 * sensitivity;
+        let half_window = ((0.002 * sample_rate) as usize).max(16);
+        let max_click_len = ((0.001 * sample_rate) as usize).max(4);
+        let guard = 2; // 计算局部均值时排除中心附近的样本
+        
+        // 计算二阶差分的绝对值
+        let mut diff2 = vec![0.0f32; len];
+        for i in 1..len - 1 {
+            diff2[i] = (audio_data[i + 1] - 2.0 * audio_data[i] + audio_data[i - 1]).abs();
+        }
+        
+        // 前缀和，用于快速计算局部均值
+        let mut prefix = vec![0.0f64; len + 1];
+        for i in 0..len {
+            prefix[i + 1] = prefix[i] + diff2[i] as f64;
+        }
+        
+        // 标记局部离群的样本
+        let mut flagged = vec![false; len];
+        for i in 1..len - 1 {
+            let lo = i.saturating_sub(half_window);
+            let hi = (i + half_window + 1).min(len);
+            let ex_lo = i.saturating_sub(guard);
+            let ex_hi = (i + guard + 1).min(len);
+            
+            let count = (hi - lo) - (ex_hi - ex_lo);
+            if count == 0 {
+                continue;
+            }
+            
+            let sum = (prefix[hi] - prefix[lo]) - (prefix[ex_hi] - prefix[ex_lo]);
+            let local_mean = (sum / count as f64) as f32;
+            
+            if diff2[i] > threshold_factor * local_mean.max(1e-6) {
+                flagged[i] = true;
+            }
+        }
+        
+        // 对每个连续的受损区域进行线性插值
+        let mut i = 1;
+        while i < len - 1 {
+            if !flagged[i] {
+                i += 1;
+                continue;
+            }
+            
+            let start = i;
+            while i < len - 1 && flagged[i] {
+                i += 1;
+            }
+            let end = i - 1;
+            
+            // 过长的区域视为真实瞬态，不做修复
+            if end - start + 1 > max_click_len {
+                continue;
+            }
+            
+            let left = start - 1;
+            let right = end + 1;
+            let left_value = audio_data[left];
+            let right_value = audio_data[right];
+            
+            for (offset, sample) in audio_data[start..=end].iter_mut().enumerate() {
+                let fraction = (start + offset - left) as f32 / (right - left) as f32;
+                *sample = left_value + (right_value - left_value) * fraction;
+            }
+        }
+    }
+    
+    // 频谱门限（按频段设置阈值的噪声门），gate_profile 中各频段的 low_hz / high_hz 单位为Hz
+    // 在STFT域中将幅度低于所在频段阈值的频点置零或衰减，阈值单位与 denoise_audio 的 noise_threshold 相同（加窗FFT幅度）
+    #[wasm_bindgen]
+    pub fn spectral_gate(&self, audio_data: &mut [f32], gate_profile: JsValue) -> Result<(), JsValue> {
+        let profile: SpectralGateProfile = parse_settings(gate_profile)?;
+        
+        let fft_size = 2048;
+        let hop_size = fft_size / 4;
+        let sample_rate = self.sample_rate as f32;
+        let attenuation = profile.attenuation.clamp(0.0, 1.0);
+        
+        // 预先计算每个频点的阈值
+        let thresholds: Vec<f32> = (0..fft_size / 2 + 1)
+            .map(|k| {
+                let freq = normalized_to_hz(k as f32 / fft_size as f32, sample_rate);
+                profile.bands
+                    .iter()
+                    .find(|band| freq >= band.low_hz && freq < band.high_hz)
+                    .map_or(profile.default_threshold, |band| band.threshold)
+            })
+            .collect();
+        
+        process_stft(audio_data, fft_size, hop_size, |spectrum| {
+            for (bin, threshold) in spectrum.iter_mut().zip(thresholds.iter()) {
+                let magnitude = (bin.re * bin.re + bin.im * bin.im).sqrt();
+                if magnitude < *threshold {
+                    bin.re *= attenuation;
+                    bin.im *= attenuation;
+                }
+            }
+        })
+    }
+    
+    // 将毫秒时长转换为样本数（至少为1）
+    fn ms_to_samples(&self, ms: f32) -> usize {
+        ((ms * self.sample_rate as f32 / 1000.0).round() as usize).max(1)
+    }
+    
+    // 估计噪声底（dBFS）：20ms短时RMS电平的第10百分位
+    // 完全为零的帧（数字静音/补零）不参与统计；没有可用帧时返回零振幅对应的电平
+    #[wasm_bindgen]
+    pub fn estimate_noise_floor(&self, audio_data: &[f32]) -> f32 {
+        let frame_size = self.ms_to_samples(20.0);
+        
+        let mut levels: Vec<f32> = audio_data
+            .chunks(frame_size)
+            .map(|frame| self.calculate_rms(frame))
+            .filter(|&rms| rms > 0.0)
+            .map(amplitude_to_db)
+            .collect();
+        
+        if levels.is_empty() {
+            return amplitude_to_db(0.0);
+        }
+        
+        levels.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        levels[(levels.len() - 1) / 10]
+    }
+    
+    // 当前输入使用的静音阈值（dBFS），见 set_auto_silence_threshold
+    fn silence_threshold_db(&self, audio_data: &[f32]) -> f32 {
+        if self.auto_silence_threshold {
+            self.estimate_noise_floor(audio_data) + AUTO_SILENCE_MARGIN_DB
+        } else {
+            SILENCE_FLOOR_DB
+        }
+    }
+    
+    // 返回逐帧特征使用的帧数和时间信息 { num_frames, hop_samples, window_samples, frame_times_sec }，
+    // 毫秒到样本数的取整及帧数计算与各特征提取函数完全一致，便于叠加显示时与特征数组逐帧对齐；
+    // frame_times_sec 为帧起点，帧中心为起点加 window_samples / 2 个样本
+    #[wasm_bindgen]
+    pub fn frame_timing(&self, num_samples: u32, hop_ms: f32, window_ms: f32) -> Result<JsValue, JsValue> {
+        let hop = self.ms_to_samples(hop_ms);
+        let num_frames = Self::frame_count(num_samples as usize, hop);
+        
+        let timing = FrameTiming {
+            num_frames,
+            hop_samples: hop,
+            window_samples: self.ms_to_samples(window_ms),
+            frame_times_sec: (0..num_frames)
+                .map(|i| (i * hop) as f32 / self.sample_rate as f32)
+                .collect(),
+        };
+        
+        Ok(serde_wasm_bindgen::to_value(&timing)?)
+    }
+    
+    // 按帧移计算帧数：第 i 帧从 i * hop 开始，最后不足一帧的部分也算一帧
+    fn frame_count(num_samples: usize, hop: usize) -> usize {
+        num_samples.div_ceil(hop)
+    }
+    
+    // 逐帧分类清浊音：0 = 静音，1 = 清音（无声辅音等），2 = 浊音
+    // 综合帧能量、过零率和自相关周期性判断，每帧分析1024个样本（不足时补零）
+    #[wasm_bindgen]
+    pub fn classify_voicing(&self, audio_data: &[f32], hop_ms: f32) -> Box<[u8]> {
+        let frame_size = 1024;
+        let hop = self.ms_to_samples(hop_ms);
+        let num_frames = Self::frame_count(audio_data.len(), hop);
+        
+        let mut periodicity = PeriodicityAnalyzer::new(frame_size);
+        let silence_db = self.silence_threshold_db(audio_data);
+        let mut frame = vec![0.0; frame_size];
+        let mut result = Vec::with_capacity(num_frames);
+        
+        for i in 0..num_frames {
+            let start = i * hop;
+            let end = (start + frame_size).min(audio_data.len());
+            let samples = &audio_data[start..end];
+            
+            // 能量低于静音下限
+            if amplitude_to_db(self.calculate_rms(samples)) < silence_db {
+                result.push(0);
+                continue;
+            }
+            
+            // 准备定长分析帧
+            frame[..samples.len()].copy_from_slice(samples);
+            frame[samples.len()..].fill(0.0);
+            
+            // 浊音需要有清晰的周期性且过零率不高
+            let zcr = self.calculate_zero_crossing_rate(samples);
+            let periodic = periodicity.analyze(&frame, self.sample_rate).0 >= 0.6;
+            
+            result.push(if periodic && zcr < 0.3 { 2 } else { 1 });
+        }
+        
+        result.into_boxed_slice()
+    }
+    
+    // 自动增益控制（AGC）：随时间调整增益，使短时RMS保持在目标电平附近
+    // 短时电平使用以当前样本为中心、长度为 window_ms 的滑动窗口计算；
+    // 提升量不超过 max_gain_db，避免把静音段和底噪放大
+    #[wasm_bindgen]
+    pub fn apply_agc(&self, audio_data: &mut [f32], target_db: f32, max_gain_db: f32, window_ms: f32) {
+        let len = audio_data.len();
+        if len == 0 {
+            return;
+        }
+        
+        let half_window = self.ms_to_samples(window_ms) / 2;
+        
+        // 平方和前缀，用于快速计算滑动窗口RMS
+        let mut prefix = vec![0.0f64; len + 1];
+        for i in 0..len {
+            prefix[i + 1] = prefix[i] + (audio_data[i] * audio_data[i]) as f64;
+        }
+        
+        for (i, sample) in audio_data.iter_mut().enumerate() {
+            let lo = i.saturating_sub(half_window);
+            let hi = (i + half_window + 1).min(len);
+            let rms = ((prefix[hi] - prefix[lo]) / (hi - lo) as f64).sqrt() as f32;
+            
+            // 计算所需增益并限制最大提升量
+            let gain_db = (target_db - amplitude_to_db(rms)).min(max_gain_db);
+            *sample *= 10.0f32.powf(gain_db / 20.0);
+        }
+    }
+    
+    // 梳状滤波器：以 fundamental_hz（单位Hz）为基频增强谐波、抑制非谐波噪声
+    // 反馈结构 y[n] = x[n] + g * y[n - D]，D = 采样率 / 基频（分数延迟线性插值）。
+    // 谐振峰位于基频的整数倍处，峰值增益为 1/(1-g)，谷值为 1/(1+g)；
+    // 输出乘以 (1-g) 归一化，使谐波处增益为1、谐波之间衰减。
+    // feedback 越接近1谐振越尖锐，为保证稳定限制在 0~0.99
+    #[wasm_bindgen]
+    pub fn apply_comb_filter(&self, audio_data: &mut [f32], fundamental_hz: f32, feedback: f32) -> Result<(), JsValue> {
+        let sample_rate = self.sample_rate as f32;
+        if !(fundamental_hz > 0.0 && fundamental_hz < sample_rate / 2.0) {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "基频必须大于0且低于奈奎斯特频率").into());
+        }
+        
+        let feedback = feedback.clamp(0.0, 0.99);
+        
+        // 延迟长度（样本），拆分为整数和小数部分
+        let delay = 1.0 / hz_to_normalized(fundamental_hz, sample_rate);
+        let int_delay = delay.floor() as usize;
+        let frac = delay - int_delay as f32;
+        
+        // 输出历史环形缓冲区
+        let size = int_delay + 2;
+        let mut history = vec![0.0f32; size];
+        let mut write_pos = 0;
+        
+        for sample in audio_data.iter_mut() {
+            // 读取 y[n - D]（相邻两个延迟样本之间线性插值）
+            let idx1 = (write_pos + size - int_delay) % size;
+            let idx2 = (write_pos + size - int_delay - 1) % size;
+            let delayed = history[idx1] * (1.0 - frac) + history[idx2] * frac;
+            
+            let output = *sample + feedback * delayed;
+            history[write_pos] = output;
+            write_pos = (write_pos + 1) % size;
+            
+            *sample = output * (1.0 - feedback);
+        }
+        
+        Ok(())
+    }
+    
+    // 检测耳语程度，返回 0~1 的分数：清晰的浊音接近0，明显的耳语接近1
+    // 对高于静音下限的每帧计算非周期性（由McLeod音高清晰度映射）和频谱平坦度，
+    // 加权合成后在所有有声帧上取平均；没有有声帧时返回0
+    #[wasm_bindgen]
+    pub fn detect_whisper(&self, audio_data: &[f32]) -> f32 {
+        let frame_size = 1024;
+        let hop = frame_size / 2;
+        if audio_data.len() < frame_size {
+            return 0.0;
+        }
+        
+        let mut detector = McLeodDetector::new(frame_size, frame_size / 2);
+        let silence_db = self.silence_threshold_db(audio_data);
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(frame_size);
+        let mut buffer = vec![0.0; frame_size];
+        let mut spectrum = r2c.make_output_vec();
+        
+        let mut score_sum = 0.0;
+        let mut active_frames = 0;
+        
+        for start in (0..=audio_data.len() - frame_size).step_by(hop) {
+            let frame = &audio_data[start..start + frame_size];
+            if amplitude_to_db(self.calculate_rms(frame)) < silence_db {
+                continue;
+            }
+            
+            // 周期性：清晰度阈值设为0以获取原始清晰度
+            let clarity = detector
+                .get_pitch(frame, self.sample_rate, 0.0, 0.0)
+                .map_or(0.0, |pitch| pitch.clarity.clamp(0.0, 1.0));
+            
+            // 频谱平坦度：功率谱几何平均与算术平均之比
+            for (i, (dst, src)) in buffer.iter_mut().zip(frame.iter()).enumerate() {
+                let window = 0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / (frame_size as f32 - 1.0)).cos());
+                *dst = src * window;
+            }
+            if r2c.process(&mut buffer, &mut spectrum).is_err() {
+                continue;
+            }
+            let powers: Vec<f32> = spectrum.iter().map(|bin| bin.re * bin.re + bin.im * bin.im + 1e-12).collect();
+            let log_mean = powers.iter().map(|p| p.ln()).sum::<f32>() / powers.len() as f32;
+            let arithmetic_mean = powers.iter().sum::<f32>() / powers.len() as f32;
+            let flatness = log_mean.exp() / arithmetic_mean;
+            
+            // 1024点窗口下浊音清晰度通常在0.6以上、噪声在0.1以下；耳语的平坦度通常在0.3以上。均映射到 0~1
+            let aperiodicity = ((0.6 - clarity) / 0.5).clamp(0.0, 1.0);
+            let frame_score = 0.6 * aperiodicity + 0.4 * (flatness / 0.3).min(1.0);
+            score_sum += frame_score;
+            active_frames += 1;
+        }
+        
+        if active_frames == 0 {
+            return 0.0;
+        }
+        
+        (score_sum / active_frames as f32).clamp(0.0, 1.0)
+    }
+    
+    // 应用单个参数化峰值均衡（freq_hz 为中心频率，单位Hz；gain_db 为增益，单位dB）
+    // 用于精确提升或衰减一个窄频段（例如突出某个共振峰），0dB 时不做任何处理
+    #[wasm_bindgen]
+    pub fn apply_peaking_eq(&self, audio_data: &mut [f32], freq_hz: f32, q: f32, gain_db: f32) -> Result<(), JsValue> {
+        let sample_rate = self.sample_rate as f32;
+        if !(freq_hz > 0.0 && freq_hz < sample_rate / 2.0) {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "中心频率必须大于0且低于奈奎斯特频率").into());
+        }
+        if q <= 0.0 {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "Q值必须大于0").into());
+        }
+        
+        if gain_db == 0.0 {
+            return Ok(());
+        }
+        
+        let mut filter = IIRFilter::peak(freq_hz, sample_rate, q, gain_db);
+        for sample in audio_data.iter_mut() {
+            *sample = filter.process(*sample);
+        }
+        
+        Ok(())
+    }
+    
+    // 按 ITU-R BS.1770 计算单声道积分响度（LUFS）
+    // K加权后以400ms、75%重叠分块，先按 -70 LUFS 绝对门限、再按比平均值低 10 LU 的相对门限筛选；
+    // 没有分块通过门限（如静音）时返回 None
+    fn integrated_loudness(&self, audio_data: &[f32]) -> Option<f32> {
+        if audio_data.is_empty() {
+            return None;
+        }
+        
+        // K加权滤波
+        let (mut shelf, mut high_pass) = k_weighting_filters(self.sample_rate as f32);
+        let weighted: Vec<f32> = audio_data.iter().map(|&s| high_pass.process(shelf.process(s))).collect();
+        
+        // 计算每个分块的均方值（音频不足一个分块时整体作为一个分块）
+        let block_size = ((0.4 * self.sample_rate as f32) as usize).clamp(1, weighted.len());
+        let hop = (block_size / 4).max(1);
+        let block_powers: Vec<f32> = (0..=weighted.len() - block_size)
+            .step_by(hop)
+            .map(|start| weighted[start..start + block_size].iter().map(|s| s * s).sum::<f32>() / block_size as f32)
+            .collect();
+        
+        let block_loudness = mean_square_to_lufs;
+        let gated_mean = |threshold: f32| {
+            let passed: Vec<f32> = block_powers.iter().cloned().filter(|&p| block_loudness(p) > threshold).collect();
+            if passed.is_empty() {
+                None
+            } else {
+                Some(passed.iter().sum::<f32>() / passed.len() as f32)
+            }
+        };
+        
+        // 绝对门限
+        let absolute_mean = gated_mean(-70.0)?;
+        // 相对门限
+        let relative_mean = gated_mean(block_loudness(absolute_mean) - 10.0)?;
+        
+        Some(block_loudness(relative_mean))
+    }
+    
+    // 按预设依次应用均衡和压缩
+    fn apply_preset(&self, audio_data: &mut [f32], preset: &ProcessingPreset) {
+        if let Some(equalizer) = &preset.equalizer {
+            self.apply_equalizer_internal(audio_data, equalizer);
+        }
+        if let Some(compressor) = &preset.compressor {
+            self.apply_compression_internal(audio_data, compressor);
+        }
+    }
+    
+    // 响度匹配的 A/B 处理对比
+    // 分别用两个预设处理输入的副本，再把两个结果匹配到相同的积分响度（LUFS），
+    // 以较安静的一方为基准只做衰减，避免匹配时产生削波。返回两个结果和各自施加的增益
+    #[wasm_bindgen]
+    pub fn process_ab_matched(&self, audio_data: &[f32], preset_a: JsValue, preset_b: JsValue) -> Result<JsValue, JsValue> {
+        let preset_a: ProcessingPreset = parse_settings(preset_a)?;
+        let preset_b: ProcessingPreset = parse_settings(preset_b)?;
+        
+        let mut output_a = audio_data.to_vec();
+        let mut output_b = audio_data.to_vec();
+        self.apply_preset(&mut output_a, &preset_a);
+        self.apply_preset(&mut output_b, &preset_b);
+        
+        // 测量两个结果的响度（静音时不做匹配）
+        let (gain_a_db, gain_b_db, loudness_lufs) = match (self.integrated_loudness(&output_a), self.integrated_loudness(&output_b)) {
+            (Some(loudness_a), Some(loudness_b)) => {
+                let target = loudness_a.min(loudness_b);
+                (target - loudness_a, target - loudness_b, target)
+            },
+            _ => (0.0, 0.0, -70.0),
+        };
+        
+        let gain_a = 10.0f32.powf(gain_a_db / 20.0);
+        let gain_b = 10.0f32.powf(gain_b_db / 20.0);
+        output_a.iter_mut().for_each(|s| *s *= gain_a);
+        output_b.iter_mut().for_each(|s| *s *= gain_b);
+        
+        let result = AbComparisonResult {
+            output_a,
+            output_b,
+            gain_a_db,
+            gain_b_db,
+            loudness_lufs,
+        };
+        
+        Ok(serde_wasm_bindgen::to_value(&result)?)
+    }
+    
+    // 降低位深（导出16位WAV等格式前使用），返回位于目标位深量化格点上的浮点值
+    // bits 位有符号量化共有 2^bits 个电平，范围为 [-1, 1 - 1/2^(bits-1)]，bits 限制在 2~24；
+    // dither 为 true 时先叠加 ±1 LSB 的三角概率分布（TPDF）抖动，避免安静段落的量化失真
+    #[wasm_bindgen]
+    pub fn quantize_to_bits(&self, audio_data: &[f32], bits: u32, dither: bool) -> Box<[f32]> {
+        let bits = bits.clamp(2, 24);
+        let levels = (1u32 << (bits - 1)) as f32;
+        let mut rng = XorShiftRng::new(0x2545_F491);
+        
+        audio_data
+            .iter()
+            .map(|&sample| {
+                let mut scaled = sample * levels;
+                if dither {
+                    // 两个均匀分布之差即为三角分布
+                    scaled += rng.next_f32() - rng.next_f32();
+                }
+                scaled.round().clamp(-levels, levels - 1.0) / levels
+            })
+            .collect::<Vec<f32>>()
+            .into_boxed_slice()
+    }
+    
+    // 保留共振峰的变调：改变音高的同时保持原有音色，使参考发音变调后仍然自然
+    // 每帧用倒谱提升（cepstral liftering）估计频谱包络，将激励谱（幅度 / 包络）按 2^(semitones/12) 在频率上伸缩，
+    // 以相位声码器方式累积各频点相位后，再乘回原始包络；噪声状（频谱平坦）帧和静音帧直接透传，避免清辅音产生金属声。
+    // 质量限制：适合 ±12 半音以内的单声部语音；相位声码器会使瞬态略微模糊，变调幅度越大包络估计误差越明显
+    #[wasm_bindgen]
+    pub fn pitch_shift_preserve_formants(&self, audio_data: &[f32], semitones: f32) -> Result<Box<[f32]>, JsValue> {
+        let mut output = audio_data.to_vec();
+        if semitones == 0.0 || audio_data.is_empty() {
+            return Ok(output.into_boxed_slice());
+        }
+        
+        let fft_size = 2048;
+        let hop_size = fft_size / 4;
+        let num_bins = fft_size / 2 + 1;
+        let ratio = 2.0f32.powf(semitones / 12.0);
+        let two_pi = 2.0 * std::f32::consts::PI;
+        
+        // 倒谱提升的截止点（约1.4ms），低于常见语音基音周期
+        let lifter = ((self.sample_rate as f32 / 700.0) as usize).clamp(8, fft_size / 4);
+        
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(fft_size);
+        let c2r = planner.plan_fft_inverse(fft_size);
+        let mut cepstrum = vec![0.0; fft_size];
+        let mut log_spectrum = r2c.make_output_vec();
+        
+        let mut prev_phase = vec![0.0f32; num_bins];
+        let mut synth_phase = vec![0.0f32; num_bins];
+        let mut magnitudes = vec![0.0f32; num_bins];
+        let mut envelope = vec![0.0f32; num_bins];
+        let mut excitation = vec![0.0f32; num_bins];
+        let mut true_freq = vec![0.0f32; num_bins];
+        let mut fft_error = false;
+        
+        process_stft(&mut output, fft_size, hop_size, |spectrum| {
+            // 分析：幅度和每个频点的真实频率（弧度/样本）
+            for k in 0..num_bins {
+                let bin = spectrum[k];
+                let phase = bin.im.atan2(bin.re);
+                let expected = two_pi * k as f32 / fft_size as f32;
+                let mut delta = phase - prev_phase[k] - expected * hop_size as f32;
+                delta -= two_pi * (delta / two_pi).round();
+                
+                magnitudes[k] = (bin.re * bin.re + bin.im * bin.im).sqrt();
+                true_freq[k] = expected + delta / hop_size as f32;
+                prev_phase[k] = phase;
+            }
+            
+            // 静音帧或噪声状帧直接透传，并让合成相位跟随原始相位
+            let power_mean = magnitudes.iter().map(|m| m * m).sum::<f32>() / num_bins as f32;
+            let log_mean = magnitudes.iter().map(|m| (m * m + 1e-12).ln()).sum::<f32>() / num_bins as f32;
+            let flatness = if power_mean > 1e-12 { log_mean.exp() / power_mean } else { 1.0 };
+            if power_mean < 1e-8 || flatness > 0.25 {
+                synth_phase.copy_from_slice(&prev_phase);
+                return;
+            }
+            
+            // 倒谱提升估计频谱包络
+            for (dst, m) in log_spectrum.iter_mut().zip(magnitudes.iter()) {
+                *dst = Complex::new((m + 1e-9).ln(), 0.0);
+            }
+            if c2r.process(&mut log_spectrum, &mut cepstrum).is_err() {
+                fft_error = true;
+                return;
+            }
+            cepstrum[lifter..fft_size - lifter + 1].fill(0.0);
+            if r2c.process(&mut cepstrum, &mut log_spectrum).is_err() {
+                fft_error = true;
+                return;
+            }
+            for (env, bin) in envelope.iter_mut().zip(log_spectrum.iter()) {
+                *env = (bin.re / fft_size as f32).exp();
+            }
+            for k in 0..num_bins {
+                excitation[k] = magnitudes[k] / envelope[k].max(1e-9);
+            }
+            
+            // 合成：目标频点 j 取激励谱在 j / ratio 处的插值，再乘回原始包络
+            for j in 0..num_bins {
+                let source = j as f32 / ratio;
+                let idx = source.floor() as usize;
+                let (new_excitation, new_freq) = if idx + 1 < num_bins {
+                    let frac = source - idx as f32;
+                    let nearest = if frac < 0.5 { idx } else { idx + 1 };
+                    (
+                        excitation[idx] * (1.0 - frac) + excitation[idx + 1] * frac,
+                        true_freq[nearest] * ratio,
+                    )
+                } else {
+                    (0.0, two_pi * j as f32 / fft_size as f32)
+                };
+                
+                synth_phase[j] += new_freq * hop_size as f32;
+                let magnitude = new_excitation * envelope[j];
+                spectrum[j] = Complex::new(magnitude * synth_phase[j].cos(), magnitude * synth_phase[j].sin());
+            }
+        })?;
+        
+        if fft_error {
+            return Err(ProcessorError::new(ErrorCode::FftFailed, "FFT处理失败").into());
+        }
+        
+        Ok(output.into_boxed_slice())
+    }
+    
+    // 逐帧计算加汉宁窗的幅度谱：第 i 帧从 i * hop 开始（帧数与 frame_count 一致），不足 fft_size 的部分补零
+    // 回调参数为帧序号、该帧的原始样本和幅度谱，供各类逐帧频谱特征共用
+    fn for_each_spectrum_frame<F>(&self, audio_data: &[f32], hop: usize, fft_size: usize, mut callback: F) -> Result<(), JsValue>
+    where
+        F: FnMut(usize, &[f32], &[f32]),
+    {
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(fft_size);
+        let mut buffer = vec![0.0; fft_size];
+        let mut spectrum = r2c.make_output_vec();
+        let mut magnitudes = vec![0.0; spectrum.len()];
+        
+        // 汉宁窗函数（与 WindowType 相同的周期窗）
+        let window = WindowType::Hann.coefficients(fft_size);
+        
+        for frame_index in 0..Self::frame_count(audio_data.len(), hop) {
+            let start = frame_index * hop;
+            let end = (start + fft_size).min(audio_data.len());
+            let samples = &audio_data[start..end];
+            
+            // 复制并加窗
+            for (j, value) in buffer.iter_mut().enumerate() {
+                *value = samples.get(j).map_or(0.0, |s| s * window[j]);
+            }
+            
+            r2c.process(&mut buffer, &mut spectrum)
+                .map_err(|_| ProcessorError::new(ErrorCode::FftFailed, "FFT处理失败"))?;
+            
+            for (magnitude, bin) in magnitudes.iter_mut().zip(spectrum.iter()) {
+                *magnitude = (bin.re * bin.re + bin.im * bin.im).sqrt();
+            }
+            
+            callback(frame_index, samples, &magnitudes);
+        }
+        
+        Ok(())
+    }
+    
+    // 逐帧计算频谱质心（Hz），窗口滑过整个缓冲区：第 i 个值对应从 i * hop 开始的1024个样本，
+    // 低于静音下限的帧为0；用于绘制亮度随时间变化的曲线
+    #[wasm_bindgen]
+    pub fn spectral_centroid_series(&self, audio_data: &[f32], hop_ms: f32) -> Result<Box<[f32]>, JsValue> {
+        let fft_size = 1024;
+        let hop = self.ms_to_samples(hop_ms);
+        let bin_width = self.sample_rate as f32 / fft_size as f32;
+        let silence_db = self.silence_threshold_db(audio_data);
+        let mut series = Vec::with_capacity(Self::frame_count(audio_data.len(), hop));
+        
+        self.for_each_spectrum_frame(audio_data, hop, fft_size, |_, samples, magnitudes| {
+            if amplitude_to_db(self.calculate_rms(samples)) < silence_db {
+                series.push(0.0);
+            } else {
+                series.push(spectral_centroid(magnitudes, bin_width));
+            }
+        })?;
+        
+        Ok(series.into_boxed_slice())
+    }
+    
+    // 逐帧计算亮度特征，返回 { times, centroid, rolloff, crest }，可用于驱动随语音亮度变化的动画
+    // centroid 为频谱质心（Hz），rolloff 为累计85%能量处的频率（Hz），crest 为幅度谱峰值与均值之比；
+    // 每帧分析1024个样本，低于静音下限的帧各项均为0
+    #[wasm_bindgen]
+    pub fn analyze_brightness_series(&self, audio_data: &[f32], hop_ms: f32) -> Result<JsValue, JsValue> {
+        let fft_size = 1024;
+        let hop = self.ms_to_samples(hop_ms);
+        let num_frames = Self::frame_count(audio_data.len(), hop);
+        let bin_width = self.sample_rate as f32 / fft_size as f32;
+        let silence_db = self.silence_threshold_db(audio_data);
+        
+        let mut series = BrightnessSeries {
+            times: Vec::with_capacity(num_frames),
+            centroid: Vec::with_capacity(num_frames),
+            rolloff: Vec::with_capacity(num_frames),
+            crest: Vec::with_capacity(num_frames),
+        };
+        
+        self.for_each_spectrum_frame(audio_data, hop, fft_size, |frame_index, samples, magnitudes| {
+            series.times.push((frame_index * hop) as f32 / self.sample_rate as f32);
+            
+            let magnitude_sum: f32 = magnitudes.iter().sum();
+            if amplitude_to_db(self.calculate_rms(samples)) < silence_db || magnitude_sum <= 0.0 {
+                series.centroid.push(0.0);
+                series.rolloff.push(0.0);
+                series.crest.push(0.0);
+                return;
+            }
+            
+            // 频谱质心
+            series.centroid.push(spectral_centroid(magnitudes, bin_width));
+            
+            // 滚降频率：累计能量达到85%的频点
+            let total_energy: f32 = magnitudes.iter().map(|m| m * m).sum();
+            let mut cumulative = 0.0;
+            let mut rolloff_bin = magnitudes.len() - 1;
+            for (k, m) in magnitudes.iter().enumerate() {
+                cumulative += m * m;
+                if cumulative >= 0.85 * total_energy {
+                    rolloff_bin = k;
+                    break;
+                }
+            }
+            series.rolloff.push(rolloff_bin as f32 * bin_width);
+            
+            // 频谱峰度（峰值与均值之比）
+            let max_magnitude = magnitudes.iter().cloned().fold(0.0, f32::max);
+            series.crest.push(max_magnitude / (magnitude_sum / magnitudes.len() as f32));
+        })?;
+        
+        Ok(serde_wasm_bindgen::to_value(&series)?)
+    }
+    
+    // 估计前两个共振峰 (F1, F2)，单位Hz
+    // 对每个浊音帧（自相关周期性 >= 0.6）：低通后降采样到约11kHz、预加重、加汉明窗，
+    // 求12阶LPC包络并取前两个峰值；最后对所有浊音帧取中位数。没有浊音帧时返回 None
+    fn estimate_formants(&self, audio_data: &[f32]) -> Option<(f32, f32)> {
+        let frame_size = 1024;
+        let hop = frame_size / 2;
+        if audio_data.len() < frame_size {
+            return None;
+        }
+        
+        let sample_rate = self.sample_rate as f32;
+        let decimation = ((sample_rate / 11025.0).round() as usize).max(1);
+        let decimated_rate = sample_rate / decimation as f32;
+        let order = 12;
+        let grid_size = 512;
+        
+        let mut periodicity = PeriodicityAnalyzer::new(frame_size);
+        let silence_db = self.silence_threshold_db(audio_data);
+        let mut f1_values = Vec::new();
+        let mut f2_values = Vec::new();
+        
+        for start in (0..=audio_data.len() - frame_size).step_by(hop) {
+            let frame = &audio_data[start..start + frame_size];
+            if amplitude_to_db(self.calculate_rms(frame)) < silence_db {
+                continue;
+            }
+            if periodicity.analyze(frame, self.sample_rate).0 < 0.6 {
+                continue;
+            }
+            
+            // 抗混叠低通后降采样
+            let mut decimated = Vec::with_capacity(frame_size / decimation + 1);
+            if decimation > 1 {
+                let cutoff = decimated_rate * 0.45;
+                let mut stage1 = IIRFilter::low_pass(cutoff, sample_rate, 0.707);
+                let mut stage2 = IIRFilter::low_pass(cutoff, sample_rate, 0.707);
+                for (i, &sample) in frame.iter().enumerate() {
+                    let filtered = stage2.process(stage1.process(sample));
+                    if i % decimation == 0 {
+                        decimated.push(filtered);
+                    }
+                }
+            } else {
+                decimated.extend_from_slice(frame);
+            }
+            
+            // 预加重并加汉明窗
+            let n = decimated.len();
+            let mut emphasized = vec![0.0; n];
+            for i in (1..n).rev() {
+                emphasized[i] = decimated[i] - 0.97 * decimated[i - 1];
+            }
+            let window = WindowType::Hamming.coefficients(n);
+            for (sample, w) in emphasized.iter_mut().zip(window.iter()) {
+                *sample *= w;
+            }
+            
+            let coefficients = match lpc_coefficients(&emphasized, order) {
+                Some(coefficients) => coefficients,
+                None => continue,
+            };
+            
+            // 在频率网格上计算LPC包络 1/|A(e^jw)|
+            let envelope: Vec<f32> = (0..grid_size)
+                .map(|g| {
+                    let omega = std::f32::consts::PI * g as f32 / grid_size as f32;
+                    let (re, im) = coefficients.iter().enumerate().fold((0.0, 0.0), |(re, im), (k, a)| {
+                        (re + a * (omega * k as f32).cos(), im - a * (omega * k as f32).sin())
+                    });
+                    1.0 / (re * re + im * im).sqrt().max(1e-9)
+                })
+                .collect();
+            
+            // 取90Hz以上的前两个峰值
+            let peaks: Vec<f32> = (1..grid_size - 1)
+                .filter(|&g| envelope[g] > envelope[g - 1] && envelope[g] >= envelope[g + 1])
+                .map(|g| g as f32 * decimated_rate / 2.0 / grid_size as f32)
+                .filter(|&freq| freq > 90.0)
+                .take(2)
+                .collect();
+            
+            if peaks.len() == 2 {
+                f1_values.push(peaks[0]);
+                f2_values.push(peaks[1]);
+            }
+        }
+        
+        if f1_values.is_empty() {
+            return None;
+        }
+        
+        let median = |values: &mut Vec<f32>| {
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            values[values.len() / 2]
+        };
+        
+        Some((median(&mut f1_values), median(&mut f2_values)))
+    }
+    
+    // 设置元音参考表 [{ vowel, f1, f2 }]（共振峰单位Hz），可按目标语言本地化
+    #[wasm_bindgen]
+    pub fn set_vowel_table(&mut self, table: JsValue) -> Result<(), JsValue> {
+        let table: Vec<VowelReference> = parse_settings(table)?;
+        if table.is_empty() {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "元音参考表不能为空").into());
+        }
+        
+        self.vowel_table = table;
+        
+        Ok(())
+    }
+    
+    // 元音识别的内部实现
+    fn classify_vowel_internal(&self, audio_data: &[f32]) -> VowelClassification {
+        let formants = self.estimate_formants(audio_data);
+        
+        let nearest = formants.and_then(|(f1, f2)| {
+            self.vowel_table
+                .iter()
+                .map(|reference| {
+                    let d1 = hz_to_bark(f1) - hz_to_bark(reference.f1);
+                    let d2 = hz_to_bark(f2) - hz_to_bark(reference.f2);
+                    (reference, (d1 * d1 + d2 * d2).sqrt())
+                })
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        });
+        
+        VowelClassification {
+            vowel: nearest.map(|(reference, _)| reference.vowel.clone()),
+            f1: formants.map(|(f1, _)| f1),
+            f2: formants.map(|(_, f2)| f2),
+            distance: nearest.map(|(_, distance)| distance),
+        }
+    }
+    
+    // 根据测得的 F1/F2 在参考表中找最接近的元音，返回 { vowel, f1, f2, distance }
+    // 距离为 F1、F2 在Bark尺度上的欧氏距离；清音或静音输入时 vowel 为 null
+    #[wasm_bindgen]
+    pub fn classify_vowel(&self, audio_data: &[f32]) -> Result<JsValue, JsValue> {
+        let result = self.classify_vowel_internal(audio_data);
+        
+        Ok(serde_wasm_bindgen::to_value(&result)?)
+    }
+    
+    // 反转极性（每个样本取反）
+    #[wasm_bindgen]
+    pub fn invert_phase(&self, audio_data: &mut [f32]) {
+        audio_data.iter_mut().for_each(|s| *s = -*s);
+    }
+    
+    // 左右声道编码为中/侧声道：M = (L + R) / 2，S = (L - R) / 2，返回 { mid, side }
+    #[wasm_bindgen]
+    pub fn encode_mid_side(&self, left: &[f32], right: &[f32]) -> Result<JsValue, JsValue> {
+        if left.len() != right.len() {
+            return Err(ProcessorError::new(ErrorCode::LengthMismatch, "左右声道长度不一致").into());
+        }
+        
+        let result = MidSideChannels {
+            mid: left.iter().zip(right).map(|(l, r)| (l + r) * 0.5).collect(),
+            side: left.iter().zip(right).map(|(l, r)| (l - r) * 0.5).collect(),
+        };
+        
+        Ok(serde_wasm_bindgen::to_value(&result)?)
+    }
+    
+    // 中/侧声道解码为左右声道：L = M + S，R = M - S，返回 { left, right }（encode_mid_side 的逆变换）
+    #[wasm_bindgen]
+    pub fn decode_mid_side(&self, mid: &[f32], side: &[f32]) -> Result<JsValue, JsValue> {
+        if mid.len() != side.len() {
+            return Err(ProcessorError::new(ErrorCode::LengthMismatch, "中侧声道长度不一致").into());
+        }
+        
+        let result = StereoChannels {
+            left: mid.iter().zip(side).map(|(m, s)| m + s).collect(),
+            right: mid.iter().zip(side).map(|(m, s)| m - s).collect(),
+        };
+        
+        Ok(serde_wasm_bindgen::to_value(&result)?)
+    }
+    
+    // 判断立体声是否实际为单声道（左右声道基本相同），可据此提前下混、跳过立体声处理
+    // 比较差值能量与总能量之比 Σ(L - R)² / Σ(L² + R²)（0 = 完全相同，1 = 不相关，2 = 反相），
+    // 不超过 tolerance 即视为单声道，例如 0.001 约相当于侧声道比中声道低30dB；两路都静音时返回 true
+    #[wasm_bindgen]
+    pub fn is_effectively_mono(&self, left: &[f32], right: &[f32], tolerance: f32) -> Result<bool, JsValue> {
+        if left.len() != right.len() {
+            return Err(ProcessorError::new(ErrorCode::LengthMismatch, "左右声道长度不一致").into());
+        }
+        
+        let (difference, total) = left.iter().zip(right).fold((0.0f64, 0.0f64), |(difference, total), (&l, &r)| {
+            let (l, r) = (l as f64, r as f64);
+            (difference + (l - r) * (l - r), total + l * l + r * r)
+        });
+        
+        Ok(total == 0.0 || difference / total <= tolerance.max(0.0) as f64)
+    }
+    
+    // 生成可直接循环播放的缓冲区：返回 [loop_start, loop_end) 区间，并在末尾预先做好接缝交叉淡化，
+    // 设为 AudioBufferSourceNode 的 buffer 并开启 loop 即可无缝循环
+    // 末尾 crossfade_ms 内，循环段逐渐淡出、loop_start 之前的同长度素材逐渐淡入（等功率曲线），
+    // 因此回到开头时接续的正是淡入素材的自然延续；交叉淡化需要 loop_start 之前有足够的素材
+    #[wasm_bindgen]
+    pub fn render_loop_buffer(&self, audio_data: &[f32], loop_start: u32, loop_end: u32, crossfade_ms: f32) -> Result<Box<[f32]>, JsValue> {
+        let loop_start = loop_start as usize;
+        let loop_end = loop_end as usize;
+        if loop_start >= loop_end || loop_end > audio_data.len() {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "循环区间无效").into());
+        }
+        
+        let crossfade = (crossfade_ms.max(0.0) * self.sample_rate as f32 / 1000.0).round() as usize;
+        if crossfade > loop_end - loop_start {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "交叉淡化长度超过循环区间").into());
+        }
+        if crossfade > loop_start {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "循环起点之前的素材不足以进行交叉淡化").into());
+        }
+        
+        let mut output = audio_data[loop_start..loop_end].to_vec();
+        let fade_start = output.len() - crossfade;
+        let pre_roll = &audio_data[loop_start - crossfade..loop_start];
+        
+        for (i, (sample, &incoming)) in output[fade_start..].iter_mut().zip(pre_roll).enumerate() {
+            let position = (i as f32 + 0.5) / crossfade as f32 * std::f32::consts::FRAC_PI_2;
+            *sample = *sample * position.cos() + incoming * position.sin();
+        }
+        
+        Ok(output.into_boxed_slice())
+    }
+    
+    // 按指定信噪比（dB）将噪声混入语音，返回与语音等长的混合结果
+    // 语音能量用门限RMS测量（calculate_rms_gated，门限与静音判定相同），只统计有语音的帧，
+    // 因此停顿不会拉低语音电平；噪声按整体RMS测量，长度不足时循环平铺，过长时截断
+    // 语音全为静音或噪声为空/全零时无法确定噪声电平，直接返回语音的副本
+    #[wasm_bindgen]
+    pub fn mix_at_snr(&self, speech: &[f32], noise: &[f32], snr_db: f32) -> Box<[f32]> {
+        let speech_rms = self.calculate_rms_gated(speech, self.silence_threshold_db(speech));
+        
+        // 平铺或截断噪声到语音长度
+        let tiled_noise: Vec<f32> = noise.iter().cycle().take(speech.len()).cloned().collect();
+        let noise_rms = self.calculate_rms(&tiled_noise);
+        
+        if speech_rms <= 0.0 || noise_rms <= 0.0 {
+            return speech.to_vec().into_boxed_slice();
+        }
+        
+        let noise_gain = speech_rms / (noise_rms * db_to_amplitude(snr_db));
+        speech
+            .iter()
+            .zip(&tiled_noise)
+            .map(|(s, n)| s + n * noise_gain)
+            .collect::<Vec<f32>>()
+            .into_boxed_slice()
+    }
+    
+    // 检测持续的窄带背景音（如投影仪、空调的啸叫），返回按强度降序排列的 [{ frequency, strength_db, persistence }]
+    // 以8192点FFT（50%重叠）逐帧寻找比周围±20个频点平均dB电平高出10dB以上的局部峰，
+    // 只保留在至少70%的非静音帧中都出现、且在平均频谱中同样突出（>= 6dB）的峰；
+    // 语音的谐波和共振峰随时间移动，不会在同一频点持续出现，因此不会被标记
+    // 结果可直接用于 apply_peaking_eq 做陷波（负增益、高Q值）
+    #[wasm_bindgen]
+    pub fn detect_tonal_noise(&self, audio_data: &[f32]) -> Result<JsValue, JsValue> {
+        let peaks = self.detect_tonal_noise_internal(audio_data)?;
+        Ok(serde_wasm_bindgen::to_value(&peaks)?)
+    }
+    
+    fn detect_tonal_noise_internal(&self, audio_data: &[f32]) -> Result<Vec<TonalNoisePeak>, JsValue> {
+        let fft_size = 8192;
+        if audio_data.len() < fft_size {
+            return Err(ProcessorError::new(ErrorCode::TooShort, "音频太短，无法检测持续音").into());
+        }
+        
+        let hop = fft_size / 2;
+        let num_bins = fft_size / 2 + 1;
+        let bin_width = self.sample_rate as f32 / fft_size as f32;
+        let half_span = 20;
+        let max_peaks = 10;
+        
+        let mut hit_counts = vec![0usize; num_bins];
+        let mut mean_db = vec![0.0f32; num_bins];
+        let mut frame_db = vec![0.0f32; num_bins];
+        let mut active_frames = 0;
+        
+        // 只分析完整的帧
+        let analysis_len = audio_data.len() - (audio_data.len() - fft_size) % hop;
+        self.for_each_spectrum_frame(&audio_data[..analysis_len], hop, fft_size, |_, samples, magnitudes| {
+            if samples.len() < fft_size || magnitudes.iter().all(|&m| m <= 0.0) {
+                return;
+            }
+            active_frames += 1;
+            
+            for ((db, mean), &magnitude) in frame_db.iter_mut().zip(mean_db.iter_mut()).zip(magnitudes) {
+                *db = amplitude_to_db(magnitude);
+                *mean += *db;
+            }
+            
+            for bin in find_prominent_peaks(&frame_db, half_span, 10.0) {
+                hit_counts[bin] += 1;
+            }
+        })?;
+        
+        let mut peaks = Vec::new();
+        if active_frames > 0 {
+            mean_db.iter_mut().for_each(|db| *db /= active_frames as f32);
+            
+            // 忽略直流附近和接近奈奎斯特频率的频点
+            let min_bin = (50.0 / bin_width).ceil() as usize;
+            let max_bin = num_bins - half_span;
+            
+            for bin in find_prominent_peaks(&mean_db, half_span, 6.0) {
+                if bin < min_bin || bin >= max_bin {
+                    continue;
+                }
+                
+                // 允许峰在相邻频点之间轻微抖动
+                let hits: usize = hit_counts[bin - 1..=bin + 1].iter().sum();
+                let persistence = (hits as f32 / active_frames as f32).min(1.0);
+                if persistence < 0.7 {
+                    continue;
+                }
+                
+                // 在平均dB频谱上做抛物线插值以细化频率
+                let (left, center, right) = (mean_db[bin - 1], mean_db[bin], mean_db[bin + 1]);
+                let denominator = left - 2.0 * center + right;
+                let offset = if denominator.abs() > 1e-6 { 0.5 * (left - right) / denominator } else { 0.0 };
+                
+                peaks.push(TonalNoisePeak {
+                    frequency: (bin as f32 + offset.clamp(-0.5, 0.5)) * bin_width,
+                    strength_db: center - local_mean(&mean_db, bin, half_span),
+                    persistence,
+                });
+            }
+        }
+        
+        peaks.sort_by(|a, b| b.strength_db.partial_cmp(&a.strength_db).unwrap_or(std::cmp::Ordering::Equal));
+        peaks.truncate(max_peaks);
+        
+        Ok(peaks)
+    }
+    
+    // 逐帧计算短时能量（RMS电平，dBFS）和过零率，返回 { times, energy_db, zcr }，用于绘制语音活动条
+    // 帧长和帧移均为 frame_ms（不重叠），第 i 帧从 i * hop 开始，最后不足一帧的部分单独成帧；
+    // times 为帧起点（秒），与 frame_timing 一致
+    #[wasm_bindgen]
+    pub fn speech_activity_series(&self, audio_data: &[f32], frame_ms: f32) -> Result<JsValue, JsValue> {
+        let hop = self.ms_to_samples(frame_ms);
+        let num_frames = Self::frame_count(audio_data.len(), hop);
+        
+        let mut series = SpeechActivitySeries {
+            times: Vec::with_capacity(num_frames),
+            energy_db: Vec::with_capacity(num_frames),
+            zcr: Vec::with_capacity(num_frames),
+        };
+        
+        for (i, frame) in audio_data.chunks(hop).enumerate() {
+            series.times.push((i * hop) as f32 / self.sample_rate as f32);
+            series.energy_db.push(amplitude_to_db(self.calculate_rms(frame)));
+            series.zcr.push(self.calculate_zero_crossing_rate(frame));
+        }
+        
+        Ok(serde_wasm_bindgen::to_value(&series)?)
+    }
+    
+    // 将音高映射为色相（0~360度），用于按颜色绘制语调曲线
+    // 在 [min_freq, max_freq] 内按对数（音程）均匀映射，每个八度占相同的色相跨度，超出范围的频率截断到端点；
+    // freq <= 0（如无声帧）或频率范围无效时返回 NaN，界面可据此跳过该点
+    #[wasm_bindgen]
+    pub fn pitch_to_hue(&self, freq: f32, min_freq: f32, max_freq: f32) -> f32 {
+        if freq <= 0.0 || min_freq <= 0.0 || max_freq <= min_freq {
+            return f32::NAN;
+        }
+        
+        let position = (freq / min_freq).ln() / (max_freq / min_freq).ln();
+        position.clamp(0.0, 1.0) * 360.0
+    }
+    
+    // pitch_to_hue 的批量版本，对音高曲线的每个点计算色相
+    #[wasm_bindgen]
+    pub fn pitch_contour_to_hues(&self, contour: &[f32], min_freq: f32, max_freq: f32) -> Box<[f32]> {
+        contour
+            .iter()
+            .map(|&freq| self.pitch_to_hue(freq, min_freq, max_freq))
+            .collect::<Vec<f32>>()
+            .into_boxed_slice()
+    }
+    
+    // 按静音切分语音片段，返回 [{ start, end }]（样本序号，左闭右开）
+    // 以20ms分帧，RMS电平低于静音阈值（见 set_auto_silence_threshold）的帧视为静音；
+    // 持续至少 min_silence_ms 的静音才作为片段边界，更短的停顿（如词间停顿）归入片段内部
+    // pre_roll_ms / post_roll_ms（省略时为0）将每个片段的起点提前、终点延后（截断到音频范围内），
+    // 避免切掉爆破音的起始和句末辅音的释放；延长后的片段各自独立使用，相互重叠时也不合并
+    #[wasm_bindgen]
+    pub fn segment_on_silence(&self, audio_data: &[f32], min_silence_ms: f32, pre_roll_ms: Option<f32>, post_roll_ms: Option<f32>) -> Result<JsValue, JsValue> {
+        let pre_roll = (pre_roll_ms.unwrap_or(0.0).max(0.0) * self.sample_rate as f32 / 1000.0).round() as usize;
+        let post_roll = (post_roll_ms.unwrap_or(0.0).max(0.0) * self.sample_rate as f32 / 1000.0).round() as usize;
+        
+        let segments: Vec<Segment> = self
+            .find_segments(audio_data, min_silence_ms)
+            .into_iter()
+            .map(|segment| Segment {
+                start: segment.start.saturating_sub(pre_roll),
+                end: (segment.end + post_roll).min(audio_data.len()),
+            })
+            .collect();
+        Ok(serde_wasm_bindgen::to_value(&segments)?)
+    }
+    
+    fn find_segments(&self, audio_data: &[f32], min_silence_ms: f32) -> Vec<Segment> {
+        let frame_size = self.ms_to_samples(20.0);
+        let min_silence_frames = (min_silence_ms.max(0.0) / 20.0).ceil() as usize;
+        let silence_db = self.silence_threshold_db(audio_data);
+        
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut current: Option<Segment> = None;
+        let mut silent_run = 0;
+        
+        for (i, frame) in audio_data.chunks(frame_size).enumerate() {
+            let start = i * frame_size;
+            let end = start + frame.len();
+            
+            if amplitude_to_db(self.calculate_rms(frame)) < silence_db {
+                silent_run += 1;
+                // 静音持续足够长时结束当前片段（片段终点为最后一个有声帧的末尾）
+                if silent_run >= min_silence_frames.max(1) {
+                    if let Some(segment) = current.take() {
+                        segments.push(segment);
+                    }
+                }
+            } else {
+                silent_run = 0;
+                match current.as_mut() {
+                    Some(segment) => segment.end = end,
+                    None => current = Some(Segment { start, end }),
+                }
+            }
+        }
+        
+        if let Some(segment) = current {
+            segments.push(segment);
+        }
+        
+        segments
+    }
+    
+    // 课程加载时的一次性预处理，返回 { waveform, features, segments }：
+    // waveform 同 generate_waveform（峰值模式，num_waveform_points 个点），
+    // features 同 analyze_audio，segments 同 segment_on_silence（最短静音300ms）；
+    // 只需跨越一次WASM边界、传递一次音频缓冲区
+    #[wasm_bindgen]
+    pub fn preprocess_clip(&self, audio_data: &[f32], num_waveform_points: u32) -> Result<JsValue, JsValue> {
+        let result = ClipPreprocessResult {
+            waveform: self.generate_waveform(audio_data, num_waveform_points, None, None).into_vec(),
+            features: self.analyze_audio_internal(audio_data),
+            segments: self.find_segments(audio_data, 300.0),
+        };
+        
+        Ok(serde_wasm_bindgen::to_value(&result)?)
+    }
+    
+    // 峰值归一化：将峰值缩放到 target_level（线性振幅，1.0 为满刻度），返回 { gain, clipped, new_peak }
+    // gain 为所用的线性增益，new_peak 为处理后的峰值；target_level 大于1.0时输出会超出满刻度，
+    // 此时 clipped 为 true（样本本身不做限幅），调用方可据此降低目标或接入限幅器。
+    // 全静音时不做处理，gain 为1.0
+    #[wasm_bindgen]
+    pub fn normalize_volume(&self, audio_data: &mut [f32], target_level: f32) -> Result<JsValue, JsValue> {
+        let result = self.normalize_volume_internal(audio_data, target_level);
+        Ok(serde_wasm_bindgen::to_value(&result)?)
+    }
+    
+    fn normalize_volume_internal(&self, audio_data: &mut [f32], target_level: f32) -> NormalizeResult {
+        let peak = self.calculate_peak(audio_data);
+        if peak <= 0.0 {
+            return NormalizeResult { gain: 1.0, clipped: false, new_peak: 0.0 };
+        }
+        
+        let gain = target_level / peak;
+        audio_data.iter_mut().for_each(|s| *s *= gain);
+        
+        let new_peak = self.calculate_peak(audio_data);
+        NormalizeResult { gain, clipped: new_peak > 1.0, new_peak }
+    }
+    
+    // 以dBFS为目标的峰值归一化（如 -1 dBFS 留出余量），返回所用增益（dB）
+    #[wasm_bindgen]
+    pub fn normalize_peak_db(&self, audio_data: &mut [f32], target_dbfs: f32) -> f32 {
+        let result = self.normalize_volume_internal(audio_data, db_to_amplitude(target_dbfs));
+        amplitude_to_db(result.gain)
+    }
+    
+    // 检测并校正两路信号之间的时间偏移（如两支话筒或两次录音），就地平移 target 使其与 reference 对齐
+    // 在 ±max_lag 个样本内用FFT互相关寻找最佳延迟，返回对 target 实际施加的平移量（样本）：
+    // 负值表示 target 原本滞后、已向前移动，正值表示已向后移动；移出的部分补零
+    // 归一化互相关峰值低于0.3（两路信号基本不相关）时不做修改并返回0
+    #[wasm_bindgen]
+    pub fn align_channels(&self, reference: &[f32], target: &mut [f32], max_lag: u32) -> i32 {
+        let lag = match cross_correlation_lag(reference, target, max_lag as usize) {
+            Some((lag, correlation)) if correlation >= 0.3 => lag,
+            _ => return 0,
+        };
+        
+        let len = target.len();
+        let shift = lag.unsigned_abs().min(len);
+        if lag > 0 {
+            // target 滞后：向前移动
+            target.copy_within(shift.., 0);
+            target[len - shift..].fill(0.0);
+        } else if lag < 0 {
+            // target 超前：向后移动
+            target.copy_within(..len - shift, shift);
+            target[..shift].fill(0.0);
+        }
+        
+        -(lag as i32)
+    }
+    
+    // 瞬态整形：分别调整起音（瞬态）和持续部分的电平，attack_gain / sustain_gain 为线性倍数
+    // 用快速（攻击1ms、释放20ms）和慢速（攻击20ms、释放200ms）两个包络跟随器，快包络超出慢包络的比例 t（0~1）即为瞬态程度，
+    // 每个样本的增益为 attack_gain^t * sustain_gain^(1-t)；两个增益均为1时完全透明
+    // 提升 attack_gain 使辅音更清晰，降低则让刺耳的录音更柔和
+    #[wasm_bindgen]
+    pub fn apply_transient_shaper(&self, audio_data: &mut [f32], attack_gain: f32, sustain_gain: f32) {
+        let sample_rate = self.sample_rate as f32;
+        let mut fast = EnvelopeFollower::new(sample_rate, 0.001, 0.02, 0);
+        let mut slow = EnvelopeFollower::new(sample_rate, 0.02, 0.2, 0);
+        let attack_gain = attack_gain.max(0.0);
+        let sustain_gain = sustain_gain.max(0.0);
+        
+        for sample in audio_data.iter_mut() {
+            let fast_envelope = fast.process(*sample);
+            let slow_envelope = slow.process(*sample);
+            
+            let transient = if fast_envelope > 1e-9 {
+                ((fast_envelope - slow_envelope) / fast_envelope).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            
+            *sample *= attack_gain.powf(transient) * sustain_gain.powf(1.0 - transient);
+        }
+    }
+    
+    // 短时响度曲线，返回 { times, lufs }（EBU R128 的短时响度使用 window_sec = 3、hop_sec 可取 0.1~1）
+    // 与积分响度相同的K加权，窗口从 i * hop 开始（帧数与 frame_timing 一致），times 为窗口起点；
+    // 超出音频末尾的窗口按补零计算（均方值仍除以完整窗长），因此结尾处的窗口会偏低
+    #[wasm_bindgen]
+    pub fn short_term_loudness_series(&self, audio_data: &[f32], window_sec: f32, hop_sec: f32) -> Result<JsValue, JsValue> {
+        let series = self.short_term_loudness_internal(audio_data, window_sec, hop_sec);
+        Ok(serde_wasm_bindgen::to_value(&series)?)
+    }
+    
+    fn short_term_loudness_internal(&self, audio_data: &[f32], window_sec: f32, hop_sec: f32) -> LoudnessSeries {
+        let window = self.ms_to_samples(window_sec * 1000.0);
+        let hop = self.ms_to_samples(hop_sec * 1000.0);
+        let num_frames = Self::frame_count(audio_data.len(), hop);
+        
+        // K加权后的平方和前缀
+        let (mut shelf, mut high_pass) = k_weighting_filters(self.sample_rate as f32);
+        let mut prefix = vec![0.0f64; audio_data.len() + 1];
+        for (i, &sample) in audio_data.iter().enumerate() {
+            let weighted = high_pass.process(shelf.process(sample));
+            prefix[i + 1] = prefix[i] + (weighted * weighted) as f64;
+        }
+        
+        let mut series = LoudnessSeries {
+            times: Vec::with_capacity(num_frames),
+            lufs: Vec::with_capacity(num_frames),
+        };
+        
+        for i in 0..num_frames {
+            let start = i * hop;
+            let end = (start + window).min(audio_data.len());
+            let power = (prefix[end] - prefix[start]) / window as f64;
+            
+            series.times.push(start as f32 / self.sample_rate as f32);
+            series.lufs.push(mean_square_to_lufs(power as f32));
+        }
+        
+        series
+    }
+    
+    // “磁带变速”：用加窗sinc插值重采样，速度和音高一起改变（speed = 0.5 时慢一倍、低一个八度），
+    // 输出长度约为 len / speed；与时间伸缩（只改变速度、保持音高）不同，部分学习者更喜欢慢速时音高降低的效果
+    // speed > 1 时sinc截止频率随之降低以防混叠；speed 必须为正数
+    #[wasm_bindgen]
+    pub fn resample_speed(&self, audio_data: &[f32], speed: f32) -> Result<Box<[f32]>, JsValue> {
+        if !(speed > 0.0 && speed.is_finite()) {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "速度必须为正数").into());
+        }
+        
+        let output_len = (audio_data.len() as f64 / speed as f64).round() as usize;
+        let output = sinc_resample(audio_data, speed as f64, output_len);
+        
+        Ok(output.into_boxed_slice())
+    }
+    
+    // 按窗口粗略区分语音和音乐，返回 { times, labels, confidence }，用于自动切换语音/音乐的处理设置
+    // 窗口内以1024点帧（帧移512）计算四项特征，各自映射为0~1的“像语音”程度后取平均得到语音得分：
+    // 低能量帧比例（语音有停顿）、高过零率帧比例（清辅音与元音交替）、频谱变化的变异系数、频谱质心的变异系数
+    // 得分 >= 0.5 标为语音，置信度为 |得分 - 0.5| * 2；低于静音阈值的窗口标为静音（置信度1）
+    #[wasm_bindgen]
+    pub fn classify_speech_music(&self, audio_data: &[f32], window_sec: f32) -> Result<JsValue, JsValue> {
+        let series = self.classify_speech_music_internal(audio_data, window_sec)?;
+        Ok(serde_wasm_bindgen::to_value(&series)?)
+    }
+    
+    fn classify_speech_music_internal(&self, audio_data: &[f32], window_sec: f32) -> Result<SpeechMusicSeries, JsValue> {
+        let fft_size = 1024;
+        let hop = fft_size / 2;
+        let bin_width = self.sample_rate as f32 / fft_size as f32;
+        let window = self.ms_to_samples(window_sec * 1000.0);
+        let silence_db = self.silence_threshold_db(audio_data);
+        
+        // 逐帧特征：能量、过零率、频谱质心、频谱变化
+        let mut energies = Vec::new();
+        let mut zcrs = Vec::new();
+        let mut centroids = Vec::new();
+        let mut fluxes = Vec::new();
+        let mut prev_magnitudes: Vec<f32> = Vec::new();
+        
+        self.for_each_spectrum_frame(audio_data, hop, fft_size, |_, samples, magnitudes| {
+            let rms = self.calculate_rms(samples);
+            energies.push(rms * rms);
+            zcrs.push(self.calculate_zero_crossing_rate(samples));
+            centroids.push(spectral_centroid(magnitudes, bin_width));
+            fluxes.push(if prev_magnitudes.is_empty() {
+                0.0
+            } else {
+                spectral_flux(&prev_magnitudes, magnitudes, FluxMode::Rectified)
+            });
+            prev_magnitudes.clear();
+            prev_magnitudes.extend_from_slice(magnitudes);
+        })?;
+        
+        let num_windows = Self::frame_count(audio_data.len(), window);
+        let mut series = SpeechMusicSeries {
+            times: Vec::with_capacity(num_windows),
+            labels: Vec::with_capacity(num_windows),
+            confidence: Vec::with_capacity(num_windows),
+        };
+        
+        for w in 0..num_windows {
+            let start = w * window;
+            let end = (start + window).min(audio_data.len());
+            series.times.push(start as f32 / self.sample_rate as f32);
+            
+            // 起点落在窗口内的帧
+            let first = start.div_ceil(hop);
+            let last = end.div_ceil(hop).min(energies.len());
+            if first >= last || amplitude_to_db(self.calculate_rms(&audio_data[start..end])) < silence_db {
+                series.labels.push(2);
+                series.confidence.push(1.0);
+                continue;
+            }
+            
+            let mean = |values: &[f32]| values.iter().sum::<f32>() / values.len() as f32;
+            let variation = |values: &[f32]| {
+                let m = mean(values);
+                if m <= 1e-12 {
+                    return 0.0;
+                }
+                let variance = values.iter().map(|v| (v - m) * (v - m)).sum::<f32>() / values.len() as f32;
+                variance.sqrt() / m
+            };
+            let fraction = |values: &[f32], predicate: &dyn Fn(f32) -> bool| {
+                values.iter().filter(|&&v| predicate(v)).count() as f32 / values.len() as f32
+            };
+            
+            let energy = &energies[first..last];
+            let zcr = &zcrs[first..last];
+            let mean_energy = mean(energy);
+            let mean_zcr = mean(zcr);
+            
+            let low_energy_ratio = fraction(energy, &|e| e < 0.5 * mean_energy);
+            let high_zcr_ratio = fraction(zcr, &|z| z > 1.5 * mean_zcr);
+            let flux_variation = variation(&fluxes[first..last]);
+            let centroid_variation = variation(&centroids[first..last]);
+            
+            let score = (((low_energy_ratio - 0.1) / 0.3).clamp(0.0, 1.0)
+                + ((high_zcr_ratio - 0.05) / 0.15).clamp(0.0, 1.0)
+                + ((flux_variation - 0.5) / 1.0).clamp(0.0, 1.0)
+                + ((centroid_variation - 0.2) / 0.4).clamp(0.0, 1.0))
+                / 4.0;
+            
+            series.labels.push(if score >= 0.5 { 1 } else { 0 });
+            series.confidence.push((score - 0.5).abs() * 2.0);
+        }
+        
+        Ok(series)
+    }
+    
+    // 按片段分别做峰值归一化，使每个 { start, end } 区域（样本序号，左闭右开）的峰值都达到 target_dbfs，
+    // 便于逐句跟读时音量一致；片段外的音频保持不变
+    // 每个片段首尾各有10ms（片段过短时为其一半长度）的线性增益过渡，避免边界处增益跳变产生咔嗒声
+    // 片段互相重叠、越界或为空时返回错误；全静音的片段保持不变
+    #[wasm_bindgen]
+    pub fn normalize_segments(&self, audio_data: &mut [f32], segments: JsValue, target_dbfs: f32) -> Result<(), JsValue> {
+        let segments: Vec<Segment> = parse_settings(segments)?;
+        self.normalize_segments_internal(audio_data, segments, target_dbfs)
+    }
+    
+    fn normalize_segments_internal(&self, audio_data: &mut [f32], mut segments: Vec<Segment>, target_dbfs: f32) -> Result<(), JsValue> {
+        segments.sort_by_key(|segment| segment.start);
+        
+        // 先整体校验，避免部分处理后才发现错误
+        for (i, segment) in segments.iter().enumerate() {
+            if segment.start >= segment.end || segment.end > audio_data.len() {
+                return Err(ProcessorError::new(ErrorCode::InvalidParameter, "片段为空或超出音频范围").into());
+            }
+            if i > 0 && segment.start < segments[i - 1].end {
+                return Err(ProcessorError::new(ErrorCode::InvalidParameter, "片段之间不能重叠").into());
+            }
+        }
+        
+        let target = db_to_amplitude(target_dbfs);
+        let ramp_len = self.ms_to_samples(10.0);
+        
+        for segment in &segments {
+            let region = &mut audio_data[segment.start..segment.end];
+            let peak = self.calculate_peak(region);
+            if peak <= 0.0 {
+                continue;
+            }
+            
+            let gain = target / peak;
+            let len = region.len();
+            let ramp = ramp_len.min(len / 2);
+            
+            for (i, sample) in region.iter_mut().enumerate() {
+                // 到片段两端的距离决定过渡进度
+                let edge_distance = i.min(len - 1 - i);
+                let sample_gain = if edge_distance < ramp {
+                    let progress = (edge_distance as f32 + 0.5) / ramp as f32;
+                    1.0 + (gain - 1.0) * progress
+                } else {
+                    gain
+                };
+                *sample *= sample_gain;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // 用任意FIR滤波器（系数即冲激响应）对音频做因果卷积，输出长度与输入相同，卷积尾部被截断
+    // 对称系数构成线性相位滤波器，其群时延为 (N-1)/2 个样本，需要对齐时可自行向前平移补偿
+    // 短滤波器直接卷积，长滤波器（>= 64 阶）用FFT分块重叠相加，两者结果在浮点误差内一致
+    #[wasm_bindgen]
+    pub fn apply_fir(&self, audio_data: &mut [f32], coefficients: &[f32]) -> Result<(), JsValue> {
+        if coefficients.is_empty() {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "FIR系数不能为空").into());
+        }
+        
+        fir_filter(audio_data, coefficients)
+    }
+    
+    // 线性相位（零相位）三段均衡：幅度响应与 apply_equalizer 相同（同样的分频点和滤波器斜率），
+    // 但只在频域按实数增益缩放各频点（STFT分帧、FFT、乘以幅度响应、IFFT、重叠相加），不改变相位，
+    // 适合对相位失真敏感的分析/转写场景。band_gains_db 为 { bass, mid, treble }（dB），全部为0时输出与输入一致
+    #[wasm_bindgen]
+    pub fn apply_linear_phase_eq(&self, audio_data: &[f32], band_gains_db: JsValue) -> Result<Box<[f32]>, JsValue> {
+        let gains: EqBandGainsDb = parse_settings(band_gains_db)?;
+        
+        Ok(self.apply_linear_phase_eq_internal(audio_data, &gains)?.into_boxed_slice())
+    }
+    
+    fn apply_linear_phase_eq_internal(&self, audio_data: &[f32], gains: &EqBandGainsDb) -> Result<Vec<f32>, JsValue> {
+        let fft_size = 4096;
+        let hop_size = fft_size / 4;
+        
+        let (bass_filter, treble_filter) = self.equalizer_filters();
+        let sample_rate = self.sample_rate as f32;
+        let bass = db_to_amplitude(gains.bass);
+        let mid = db_to_amplitude(gains.mid);
+        let treble = db_to_amplitude(gains.treble);
+        
+        // 每个频点的目标幅度（与 compute_eq_response 相同的组合方式，只取模）
+        let magnitude_response: Vec<f32> = self
+            .bin_frequencies(fft_size)
+            .iter()
+            .map(|&freq| {
+                (Complex::new(mid, 0.0)
+                    + bass_filter.response(freq, sample_rate) * (bass - mid)
+                    + treble_filter.response(freq, sample_rate) * (treble - mid))
+                    .norm()
+            })
+            .collect();
+        
+        let mut output = audio_data.to_vec();
+        process_stft(&mut output, fft_size, hop_size, |spectrum| {
+            for (bin, &gain) in spectrum.iter_mut().zip(magnitude_response.iter()) {
+                *bin *= gain;
+            }
+        })?;
+        
+        Ok(output)
+    }
+    
+    // 测量总谐波失真（THD）：各次谐波（2f、3f……直到奈奎斯特频率）幅度的平方和开方后与基波幅度之比，
+    // 返回比值（0.01 即 1%）。纯正弦接近0，削波后明显升高，可用于检查参考音和软削波/饱和效果
+    // 取音频中间最多65536点（2的幂）加Blackman-Harris窗做FFT，每个谐波的能量为其主瓣内各频点之和；
+    // 音频不足1024点、基波无效或基波能量为0时返回0
+    #[wasm_bindgen]
+    pub fn measure_thd(&self, audio_data: &[f32], fundamental_hz: f32) -> f32 {
+        let nyquist = self.sample_rate as f32 / 2.0;
+        if audio_data.len() < 1024 || !(fundamental_hz > 0.0 && fundamental_hz < nyquist) {
+            return 0.0;
+        }
+        
+        let fft_size = if audio_data.len().is_power_of_two() {
+            audio_data.len()
+        } else {
+            audio_data.len().next_power_of_two() / 2
+        }
+        .min(65536);
+        let start = (audio_data.len() - fft_size) / 2;
+        let window = WindowType::BlackmanHarris.coefficients(fft_size);
+        
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(fft_size);
+        let mut buffer: Vec<f32> = audio_data[start..start + fft_size]
+            .iter()
+            .zip(window.iter())
+            .map(|(sample, w)| sample * w)
+            .collect();
+        let mut spectrum = r2c.make_output_vec();
+        if r2c.process(&mut buffer, &mut spectrum).is_err() {
+            return 0.0;
+        }
+        let powers: Vec<f32> = spectrum.iter().map(|bin| bin.re * bin.re + bin.im * bin.im).collect();
+        
+        // Blackman-Harris 主瓣半宽为4个频点；基波过低时收窄，避免相邻谐波的能量重叠
+        let bin_width = self.sample_rate as f32 / fft_size as f32;
+        let half_width = ((fundamental_hz / bin_width / 2.0) as usize).clamp(1, 4);
+        let harmonic_power = |freq: f32| {
+            let center = (freq / bin_width).round() as usize;
+            let lo = center.saturating_sub(half_width);
+            let hi = (center + half_width).min(powers.len() - 1);
+            powers[lo..=hi].iter().sum::<f32>()
+        };
+        
+        let fundamental_power = harmonic_power(fundamental_hz);
+        if fundamental_power <= 0.0 {
+            return 0.0;
+        }
+        
+        let mut harmonics_power = 0.0;
+        let mut harmonic = 2.0;
+        while fundamental_hz * harmonic < nyquist {
+            harmonics_power += harmonic_power(fundamental_hz * harmonic);
+            harmonic += 1.0;
+        }
+        
+        (harmonics_power / fundamental_power).sqrt()
+    }
+    
+    // 逐窗口互相关得到跟读录音（attempt）相对参考录音（reference）的局部延迟，返回 { times, lag_sec, confidence }，
+    // 用于可视化学习者在哪里抢拍、在哪里拖慢。窗口长 window_ms、帧移 hop_ms，沿参考录音排列，
+    // 每个窗口与跟读录音同一位置的窗口在 ±window_ms/2 内搜索最佳延迟；任一方该窗口低于静音阈值时延迟记为0、置信度为0
+    #[wasm_bindgen]
+    pub fn local_alignment_map(&self, reference: &[f32], attempt: &[f32], window_ms: f32, hop_ms: f32) -> Result<JsValue, JsValue> {
+        let map = self.local_alignment_map_internal(reference, attempt, window_ms, hop_ms);
+        
+        Ok(serde_wasm_bindgen::to_value(&map)?)
+    }
+    
+    fn local_alignment_map_internal(&self, reference: &[f32], attempt: &[f32], window_ms: f32, hop_ms: f32) -> AlignmentMap {
+        let window = self.ms_to_samples(window_ms);
+        let hop = self.ms_to_samples(hop_ms);
+        let max_lag = window / 2;
+        let sample_rate = self.sample_rate as f32;
+        let num_frames = Self::frame_count(reference.len(), hop);
+        
+        let reference_silence_db = self.silence_threshold_db(reference);
+        let attempt_silence_db = self.silence_threshold_db(attempt);
+        
+        let mut map = AlignmentMap {
+            times: Vec::with_capacity(num_frames),
+            lag_sec: Vec::with_capacity(num_frames),
+            confidence: Vec::with_capacity(num_frames),
+        };
+        
+        for i in 0..num_frames {
+            let start = i * hop;
+            let reference_window = &reference[start..(start + window).min(reference.len())];
+            let attempt_window = &attempt[start.min(attempt.len())..(start + window).min(attempt.len())];
+            
+            let audible = !attempt_window.is_empty()
+                && amplitude_to_db(self.calculate_rms(reference_window)) >= reference_silence_db
+                && amplitude_to_db(self.calculate_rms(attempt_window)) >= attempt_silence_db;
+            let (lag, confidence) = if audible {
+                cross_correlation_lag(reference_window, attempt_window, max_lag).unwrap_or((0, 0.0))
+            } else {
+                (0, 0.0)
+            };
+            
+            map.times.push(start as f32 / sample_rate);
+            map.lag_sec.push(lag as f32 / sample_rate);
+            map.confidence.push(confidence.clamp(0.0, 1.0));
+        }
+        
+        map
+    }
+    
+    // 一次性给出跟读发音评分 { overall_score, spectral_similarity, pitch_similarity, rhythm_similarity, duration_ratio, alignment_path }
+    // 两段录音先各自去掉首尾静音，按10ms帧移提取Bark频带对数能量（减去频带均值以消除音量和话筒差异）和浊音帧音高，再用DTW对齐：
+    // - spectral_similarity：对齐路径上每帧频带能量的均方根差 d（dB），得分 100 * exp(-d / 10)
+    // - pitch_similarity：路径上双方都是浊音的帧，各自去掉平均音高后比较半音曲线，均方根差 s，得分 100 * (1 - s / 4)；
+    //   这类帧少于5个时为0
+    // - rhythm_similarity：路径点在两段录音内的相对位置之差的平均值 r（0~1），得分 100 * (1 - r / 0.2)
+    // - duration_ratio 换算的时长得分为 100 * (1 - |log2(比值)|)，即快一倍或慢一倍时为0
+    // overall_score = 0.4 * 频谱 + 0.3 * 音高 + 0.2 * 节奏 + 0.1 * 时长，各得分均截断到 0~100
+    // DTW需要 帧数 × 帧数 的空间，适合句子级别（数十秒以内）的录音
+    #[wasm_bindgen]
+    pub fn score_pronunciation(&self, reference: &[f32], attempt: &[f32]) -> Result<JsValue, JsValue> {
+        let report = self.score_pronunciation_internal(reference, attempt)?;
+        
+        Ok(serde_wasm_bindgen::to_value(&report)?)
+    }
+    
+    fn score_pronunciation_internal(&self, reference: &[f32], attempt: &[f32]) -> Result<PronunciationReport, JsValue> {
+        let hop = self.ms_to_samples(10.0);
+        let hop_sec = hop as f32 / self.sample_rate as f32;
+        let reference_features = self.utterance_features(reference, hop)?;
+        let attempt_features = self.utterance_features(attempt, hop)?;
+        let n = reference_features.bands.len();
+        let m = attempt_features.bands.len();
+        
+        // DTW：逐行计算累计代价，记录每个格子的来源方向用于回溯（0 = 对角，1 = 上，2 = 左）
+        let frame_distance = |i: usize, j: usize| {
+            let a = &reference_features.bands[i];
+            let b = &attempt_features.bands[j];
+            let sum: f32 = a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum();
+            (sum / a.len() as f32).sqrt()
+        };
+        let mut directions = vec![0u8; n * m];
+        let mut prev_row = vec![f32::INFINITY; m];
+        let mut row = vec![0.0f32; m];
+        for i in 0..n {
+            for j in 0..m {
+                let (best, direction) = if i == 0 && j == 0 {
+                    (0.0, 0)
+                } else {
+                    let diagonal = if i > 0 && j > 0 { prev_row[j - 1] } else { f32::INFINITY };
+                    let up = if i > 0 { prev_row[j] } else { f32::INFINITY };
+                    let left = if j > 0 { row[j - 1] } else { f32::INFINITY };
+                    if diagonal <= up && diagonal <= left {
+                        (diagonal, 0)
+                    } else if up <= left {
+                        (up, 1)
+                    } else {
+                        (left, 2)
+                    }
+                };
+                row[j] = best + frame_distance(i, j);
+                directions[i * m + j] = direction;
+            }
+            std::mem::swap(&mut prev_row, &mut row);
+        }
+        let total_cost = prev_row[m - 1];
+        
+        let mut path = Vec::with_capacity(n + m);
+        let (mut i, mut j) = (n - 1, m - 1);
+        loop {
+            path.push((i, j));
+            if i == 0 && j == 0 {
+                break;
+            }
+            match directions[i * m + j] {
+                0 => {
+                    i -= 1;
+                    j -= 1;
+                }
+                1 => i -= 1,
+                _ => j -= 1,
+            }
+        }
+        path.reverse();
+        
+        let spectral_similarity = 100.0 * (-(total_cost / path.len() as f32) / 10.0).exp();
+        
+        // 语调：双方都是浊音的路径点，各自去掉均值后比较
+        let pitch_pairs: Vec<(f32, f32)> = path
+            .iter()
+            .filter_map(|&(i, j)| Some((reference_features.pitch[i]?, attempt_features.pitch[j]?)))
+            .collect();
+        let pitch_similarity = if pitch_pairs.len() < 5 {
+            0.0
+        } else {
+            let count = pitch_pairs.len() as f32;
+            let reference_mean = pitch_pairs.iter().map(|p| p.0).sum::<f32>() / count;
+            let attempt_mean = pitch_pairs.iter().map(|p| p.1).sum::<f32>() / count;
+            let mean_square = pitch_pairs
+                .iter()
+                .map(|&(r, a)| {
+                    let diff = (r - reference_mean) - (a - attempt_mean);
+                    diff * diff
+                })
+                .sum::<f32>() / count;
+            100.0 * (1.0 - mean_square.sqrt() / 4.0)
+        };
+        
+        // 节奏：匀速跟读时路径点在两段录音中的相对位置相同
+        let position = |index: usize, len: usize| if len > 1 { index as f32 / (len - 1) as f32 } else { 0.0 };
+        let deviation = path
+            .iter()
+            .map(|&(i, j)| (position(i, n) - position(j, m)).abs())
+            .sum::<f32>() / path.len() as f32;
+        let rhythm_similarity = 100.0 * (1.0 - deviation / 0.2);
+        
+        let duration_ratio = m as f32 / n as f32;
+        let duration_score = 100.0 * (1.0 - duration_ratio.log2().abs());
+        
+        let spectral_similarity = spectral_similarity.clamp(0.0, 100.0);
+        let pitch_similarity = pitch_similarity.clamp(0.0, 100.0);
+        let rhythm_similarity = rhythm_similarity.clamp(0.0, 100.0);
+        let overall_score = 0.4 * spectral_similarity
+            + 0.3 * pitch_similarity
+            + 0.2 * rhythm_similarity
+            + 0.1 * duration_score.clamp(0.0, 100.0);
+        
+        Ok(PronunciationReport {
+            overall_score,
+            spectral_similarity,
+            pitch_similarity,
+            rhythm_similarity,
+            duration_ratio,
+            alignment_path: path
+                .iter()
+                .map(|&(i, j)| [
+                    reference_features.start_sec + i as f32 * hop_sec,
+                    attempt_features.start_sec + j as f32 * hop_sec,
+                ])
+                .collect(),
+        })
+    }
+    
+    // 提取发音评分用的逐帧特征（1024点帧），去掉首尾的静音帧；音频太短或全为静音时返回错误
+    fn utterance_features(&self, audio_data: &[f32], hop: usize) -> Result<UtteranceFeatures, JsValue> {
+        let frame_size = 1024;
+        if audio_data.len() < frame_size {
+            return Err(ProcessorError::new(ErrorCode::TooShort, "音频太短，无法评分").into());
+        }
+        
+        // 1~21 Bark（约100Hz~7kHz）每1 Bark一个频带
+        let num_bands = 20;
+        let band_of_bin: Vec<Option<usize>> = self
+            .bin_frequencies(frame_size)
+            .iter()
+            .map(|&freq| {
+                let bark = hz_to_bark(freq);
+                (bark >= 1.0 && bark < (num_bands + 1) as f32).then(|| bark as usize - 1)
+            })
+            .collect();
+        
+        let silence_db = self.silence_threshold_db(audio_data);
+        let mut periodicity = PeriodicityAnalyzer::new(frame_size);
+        let mut frame = vec![0.0; frame_size];
+        let mut active = Vec::new();
+        let mut bands = Vec::new();
+        let mut pitch = Vec::new();
+        
+        self.for_each_spectrum_frame(audio_data, hop, frame_size, |_, samples, magnitudes| {
+            let is_active = amplitude_to_db(self.calculate_rms(samples)) >= silence_db;
+            
+            let mut energies = vec![0.0f32; num_bands];
+            for (magnitude, band) in magnitudes.iter().zip(band_of_bin.iter()) {
+                if let Some(band) = band {
+                    energies[*band] += magnitude * magnitude;
+                }
+            }
+            bands.push(energies.iter().map(|e| 10.0 * (e + 1e-10).log10()).collect::<Vec<f32>>());
+            
+            frame[..samples.len()].copy_from_slice(samples);
+            frame[samples.len()..].fill(0.0);
+            let (strength, freq) = periodicity.analyze(&frame, self.sample_rate);
+            pitch.push((is_active && strength >= 0.6 && freq > 0.0).then(|| 12.0 * (freq / 440.0).log2()));
+            
+            active.push(is_active);
+        })?;
+        
+        let first = active.iter().position(|&a| a);
+        let last = active.iter().rposition(|&a| a);
+        let (first, last) = match (first, last) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(ProcessorError::new(ErrorCode::AnalysisFailed, "未检测到有效语音，无法评分").into()),
+        };
+        
+        let mut bands = bands[first..=last].to_vec();
+        let pitch = pitch[first..=last].to_vec();
+        
+        // 减去各频带均值，消除整体音量和频率响应的差异
+        let count = bands.len() as f32;
+        for band in 0..num_bands {
+            let mean = bands.iter().map(|frame| frame[band]).sum::<f32>() / count;
+            for frame in bands.iter_mut() {
+                frame[band] -= mean;
+            }
+        }
+        
+        Ok(UtteranceFeatures {
+            start_sec: (first * hop) as f32 / self.sample_rate as f32,
+            bands,
+            pitch,
+        })
+    }
+    
+    // 解析信号（Hilbert）包络：对整段音频做FFT，把负频率置零（等价于在正频率上乘以 -j 得到Hilbert变换），
+    // 逆变换后包络为 sqrt(x² + H{x}²)。比整流后平滑的包络更平滑且没有滞后，适合绘制幅度包络和检测幅度调制；
+    // 对稳定的正弦波，包络在除首尾几个周期外基本恒定，等于其幅度。FFT长度为不小于音频长度的2的幂
+    #[wasm_bindgen]
+    pub fn hilbert_envelope(&self, audio_data: &[f32]) -> Result<Box<[f32]>, JsValue> {
+        let len = audio_data.len();
+        if len == 0 {
+            return Ok(Vec::new().into_boxed_slice());
+        }
+        
+        let fft_size = len.next_power_of_two().max(2);
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(fft_size);
+        let c2r = planner.plan_fft_inverse(fft_size);
+        
+        let mut buffer = vec![0.0; fft_size];
+        buffer[..len].copy_from_slice(audio_data);
+        let mut spectrum = r2c.make_output_vec();
+        r2c.process(&mut buffer, &mut spectrum)
+            .map_err(|_| ProcessorError::new(ErrorCode::FftFailed, "FFT处理失败"))?;
+        
+        // Hilbert变换：正频率乘以 -j，直流和奈奎斯特频点为0
+        for bin in spectrum.iter_mut() {
+            *bin = Complex::new(bin.im, -bin.re);
+        }
+        spectrum[0] = Complex::new(0.0, 0.0);
+        if let Some(last) = spectrum.last_mut() {
+            *last = Complex::new(0.0, 0.0);
+        }
+        c2r.process(&mut spectrum, &mut buffer)
+            .map_err(|_| ProcessorError::new(ErrorCode::FftFailed, "IFFT处理失败"))?;
+        
+        // 逆变换未归一化，需除以 fft_size
+        let scale = 1.0 / fft_size as f32;
+        Ok(audio_data
+            .iter()
+            .zip(buffer.iter())
+            .map(|(&x, &h)| {
+                let h = h * scale;
+                (x * x + h * h).sqrt()
+            })
+            .collect::<Vec<f32>>()
+            .into_boxed_slice())
+    }
+    
+    // 返回多个音高候选 [{ frequency, clarity }, ...]，按清晰度从高到低排列，最多 max_candidates 个，用于诊断八度错误等
+    // 与 detect_pitch 使用同一个1024点分析窗口（见 set_pitch_window_*），计算McLeod的归一化平方差函数（NSDF）
+    // n(τ) = 2 Σ x[j]x[j+τ] / Σ (x[j]² + x[j+τ]²)，在每段正值区间取最大值（key maximum），抛物线插值得到精确延迟；
+    // 音频不足1024点或没有正峰值时返回空数组
+    #[wasm_bindgen]
+    pub fn pitch_candidates(&self, audio_data: &[f32], max_candidates: u32) -> Result<JsValue, JsValue> {
+        let candidates = self.pitch_candidates_internal(audio_data, max_candidates as usize)?;
+        
+        Ok(serde_wasm_bindgen::to_value(&candidates)?)
+    }
+    
+    fn pitch_candidates_internal(&self, audio_data: &[f32], max_candidates: usize) -> Result<Vec<PitchCandidate>, JsValue> {
+        let window = 1024;
+        if audio_data.len() < window || max_candidates == 0 {
+            return Ok(Vec::new());
+        }
+        
+        let start = self.pitch_window_start(audio_data, window);
+        let frame = &audio_data[start..start + window];
+        
+        // FFT计算自相关 r(τ)
+        let fft_size = 2 * window;
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(fft_size);
+        let c2r = planner.plan_fft_inverse(fft_size);
+        let mut buffer = vec![0.0; fft_size];
+        buffer[..window].copy_from_slice(frame);
+        let mut spectrum = r2c.make_output_vec();
+        r2c.process(&mut buffer, &mut spectrum)
+            .map_err(|_| ProcessorError::new(ErrorCode::FftFailed, "FFT处理失败"))?;
+        for bin in spectrum.iter_mut() {
+            *bin = Complex::new(bin.norm_sqr(), 0.0);
+        }
+        c2r.process(&mut spectrum, &mut buffer)
+            .map_err(|_| ProcessorError::new(ErrorCode::FftFailed, "IFFT处理失败"))?;
+        
+        // NSDF：m(τ) 由总能量逐步减去移出重叠区的首尾样本能量
+        let mut m: f32 = 2.0 * frame.iter().map(|x| x * x).sum::<f32>();
+        let mut nsdf = vec![0.0f32; window];
+        for tau in 0..window {
+            if tau > 0 {
+                m -= frame[tau - 1] * frame[tau - 1] + frame[window - tau] * frame[window - tau];
+            }
+            nsdf[tau] = if m > 1e-9 { 2.0 * buffer[tau] / fft_size as f32 / m } else { 0.0 };
+        }
+        
+        // 跳过零延迟附近的主峰，之后每段正值区间取一个最大值
+        let mut candidates = Vec::new();
+        let mut tau = nsdf.iter().position(|&v| v <= 0.0).unwrap_or(window);
+        while tau < window {
+            // 找下一段正值区间
+            while tau < window && nsdf[tau] <= 0.0 {
+                tau += 1;
+            }
+            let mut best = tau;
+            while tau < window && nsdf[tau] > 0.0 {
+                if nsdf[tau] > nsdf[best] {
+                    best = tau;
+                }
+                tau += 1;
+            }
+            if best >= window || best + 1 >= window {
+                break;
+            }
+            
+            // 抛物线插值
+            let (a, b, c) = (nsdf[best - 1], nsdf[best], nsdf[best + 1]);
+            let denominator = a - 2.0 * b + c;
+            let (offset, peak) = if denominator.abs() > 1e-12 {
+                let offset = 0.5 * (a - c) / denominator;
+                (offset, b - 0.25 * (a - c) * offset)
+            } else {
+                (0.0, b)
+            };
+            candidates.push(PitchCandidate {
+                frequency: self.sample_rate as f32 / (best as f32 + offset),
+                clarity: peak.min(1.0),
+            });
+        }
+        
+        // 周期信号在整数倍周期处的清晰度几乎相同，按0.001量化后相同时优先较高的频率（较短的周期），与McLeod的选择一致
+        let quantized = |clarity: f32| (clarity * 1000.0).round() as i32;
+        candidates.sort_by(|a, b| {
+            quantized(b.clarity)
+                .cmp(&quantized(a.clarity))
+                .then(b.frequency.total_cmp(&a.frequency))
+        });
+        candidates.truncate(max_candidates);
+        
+        Ok(candidates)
+    }
+    
+    // 生成低采样率的概览副本（如8kHz），供长文件在完整数据加载完成前快速、低内存地拖动试听
+    // 抗混叠：两级级联的二阶巴特沃斯型低通（Q 为 0.541 和 1.307，合成四阶巴特沃斯响应），截止频率为目标采样率的0.45倍，
+    // 之后按 source_rate / target_rate 的步长线性插值抽取。为速度牺牲了质量：过渡带较宽、滤波器非线性相位，
+    // 高保真的变速/重采样请使用 resample_speed。target_rate >= source_rate 时原样返回副本
+    #[wasm_bindgen]
+    pub fn downsample_overview(&self, audio_data: &[f32], target_rate: u32, source_rate: u32) -> Result<Box<[f32]>, JsValue> {
+        if target_rate == 0 || source_rate == 0 {
+            return Err(ProcessorError::new(ErrorCode::BadSampleRate, "采样率必须大于0").into());
+        }
+        if target_rate >= source_rate {
+            return Ok(audio_data.to_vec().into_boxed_slice());
+        }
+        
+        let source = source_rate as f32;
+        let cutoff = 0.45 * target_rate as f32;
+        let mut stage1 = IIRFilter::low_pass(cutoff, source, 0.541_196_1);
+        let mut stage2 = IIRFilter::low_pass(cutoff, source, 1.306_563);
+        let filtered: Vec<f32> = audio_data
+            .iter()
+            .map(|&sample| stage2.process(stage1.process(sample)))
+            .collect();
+        
+        let step = source_rate as f64 / target_rate as f64;
+        let output_len = (audio_data.len() as f64 / step).round() as usize;
+        let output: Vec<f32> = (0..output_len)
+            .map(|n| {
+                let position = n as f64 * step;
+                let index = position.floor() as usize;
+                let frac = (position - index as f64) as f32;
+                let current = filtered.get(index).copied().unwrap_or(0.0);
+                let next = filtered.get(index + 1).copied().unwrap_or(current);
+                current + (next - current) * frac
+            })
+            .collect();
+        
+        Ok(output.into_boxed_slice())
+    }
+    
+    // 上传录音前的质量检查，返回 { dc_offset, has_dc_offset, clipping_ratio, is_clipped, silence_ratio, mostly_silent,
+    // estimated_snr_db, low_snr, too_short }，供界面提示“声音太小/削波/大部分是静音，请重录”
+    // - 直流偏移：样本均值；削波：绝对值达到 clip_level 的样本比例
+    // - 静音比例：20ms帧中RMS电平低于静音阈值（见 set_auto_silence_threshold）的比例
+    // - 估计信噪比：高于静音阈值各帧的门限RMS电平减去噪声底（estimate_noise_floor），没有有声帧时为0
+    // thresholds 可覆盖 RecordingQualityThresholds 中的任意阈值，传 undefined / null 时全部使用默认值
+    #[wasm_bindgen]
+    pub fn check_recording_quality(&self, audio_data: &[f32], thresholds: JsValue) -> Result<JsValue, JsValue> {
+        let thresholds: RecordingQualityThresholds = if thresholds.is_undefined() || thresholds.is_null() {
+            RecordingQualityThresholds::default()
+        } else {
+            parse_settings(thresholds)?
+        };
+        
+        let report = self.check_recording_quality_internal(audio_data, &thresholds);
+        
+        Ok(serde_wasm_bindgen::to_value(&report)?)
+    }
+    
+    // 估计信噪比（dB）：高于静音阈值各帧的门限RMS电平减去噪声底，没有有声帧时为0
+    fn estimate_snr_db(&self, audio_data: &[f32]) -> f32 {
+        let signal_rms = self.calculate_rms_gated(audio_data, self.silence_threshold_db(audio_data));
+        if signal_rms > 0.0 {
+            (amplitude_to_db(signal_rms) - self.estimate_noise_floor(audio_data)).max(0.0)
+        } else {
+            0.0
+        }
+    }
+    
+    fn check_recording_quality_internal(&self, audio_data: &[f32], thresholds: &RecordingQualityThresholds) -> RecordingQualityReport {
+        let len = audio_data.len().max(1) as f32;
+        
+        let dc_offset = (audio_data.iter().map(|&x| x as f64).sum::<f64>() / len as f64) as f32;
+        let clipped = audio_data.iter().filter(|x| x.abs() >= thresholds.clip_level).count();
+        let clipping_ratio = clipped as f32 / len;
+        
+        let silence_db = self.silence_threshold_db(audio_data);
+        let frame_size = self.ms_to_samples(20.0);
+        let num_frames = Self::frame_count(audio_data.len(), frame_size);
+        let silent_frames = audio_data
+            .chunks(frame_size)
+            .filter(|frame| amplitude_to_db(self.calculate_rms(frame)) < silence_db)
+            .count();
+        let silence_ratio = if num_frames > 0 { silent_frames as f32 / num_frames as f32 } else { 1.0 };
+        
+        let estimated_snr_db = self.estimate_snr_db(audio_data);
+        
+        let duration_sec = audio_data.len() as f32 / self.sample_rate as f32;
+        
+        RecordingQualityReport {
+            dc_offset,
+            has_dc_offset: dc_offset.abs() > thresholds.max_dc_offset,
+            clipping_ratio,
+            is_clipped: clipping_ratio > thresholds.max_clipping_ratio,
+            silence_ratio,
+            mostly_silent: silence_ratio > thresholds.max_silence_ratio,
+            estimated_snr_db,
+            low_snr: estimated_snr_db < thresholds.min_snr_db,
+            too_short: duration_sec < thresholds.min_duration_sec,
+        }
+    }
+    
+    // 分析音频并推荐一套保守的处理设置 { normalize_gain_db, suggested_hpf_hz, needs_denoise, suggested_compressor, ... }，
+    // 同时返回所用的测量值和 reasons（每条建议的中文理由），供初学者一键配置处理链：
+    // - 增益：把积分响度调到 -16 LUFS（语音常用目标），但峰值不超过 -1 dBFS；相差不到1dB时不调整
+    // - 高通：80Hz以下能量超过总能量的10%时建议 80Hz 高通去除低频隆隆声
+    // - 降噪：估计信噪比低于20dB时建议降噪
+    // - 压缩：峰值因数（峰值与门限RMS之差）超过18dB时建议温和压缩（3:1，阈值为门限RMS电平 + 6dB，自动补偿增益）
+    #[wasm_bindgen]
+    pub fn suggest_processing(&self, audio_data: &[f32]) -> Result<JsValue, JsValue> {
+        let suggestion = self.suggest_processing_internal(audio_data)?;
+        
+        Ok(serde_wasm_bindgen::to_value(&suggestion)?)
+    }
+    
+    fn suggest_processing_internal(&self, audio_data: &[f32]) -> Result<ProcessingSuggestion, JsValue> {
+        let mut reasons = Vec::new();
+        let loudness_lufs = self.integrated_loudness(audio_data);
+        let peak_dbfs = amplitude_to_db(self.calculate_peak(audio_data));
+        let silence_db = self.silence_threshold_db(audio_data);
+        let speech_rms_db = amplitude_to_db(self.calculate_rms_gated(audio_data, silence_db));
+        let noise_floor_db = self.estimate_noise_floor(audio_data);
+        let estimated_snr_db = self.estimate_snr_db(audio_data);
+        
+        // 增益：响度目标与峰值余量中较小的一个
+        let mut normalize_gain_db = 0.0;
+        if let Some(loudness) = loudness_lufs {
+            let wanted = -16.0 - loudness;
+            let gain = wanted.min(-1.0 - peak_dbfs);
+            if gain.abs() >= 1.0 {
+                normalize_gain_db = gain;
+                reasons.push(if gain < wanted {
+                    format!("响度 {:.1} LUFS，受峰值限制只建议调整 {:+.1}dB 以保留 -1dBFS 余量", loudness, gain)
+                } else {
+                    format!("响度 {:.1} LUFS，调整 {:+.1}dB 达到 -16 LUFS", loudness, gain)
+                });
+            } else if wanted >= 1.0 {
+                reasons.push(format!("响度 {:.1} LUFS 偏低，但峰值已接近 -1dBFS，建议先压缩再提升增益", loudness));
+            }
+        } else {
+            reasons.push("无法测量积分响度（音频过短或几乎为静音），不建议调整增益".to_string());
+        }
+        
+        // 低频隆隆声：平均功率谱中80Hz以下的能量比例
+        let fft_size = 4096;
+        let bin_width = self.sample_rate as f32 / fft_size as f32;
+        let mut low_energy = 0.0f64;
+        let mut total_energy = 0.0f64;
+        self.for_each_spectrum_frame(audio_data, fft_size / 2, fft_size, |_, _, magnitudes| {
+            for (k, magnitude) in magnitudes.iter().enumerate().skip(1) {
+                let power = (magnitude * magnitude) as f64;
+                total_energy += power;
+                if (k as f32) * bin_width < 80.0 {
+                    low_energy += power;
+                }
+            }
+        })?;
+        let rumble_ratio = if total_energy > 0.0 { (low_energy / total_energy) as f32 } else { 0.0 };
+        let suggested_hpf_hz = if rumble_ratio > 0.1 {
+            reasons.push(format!("80Hz以下能量占 {:.0}%，建议 80Hz 高通去除低频隆隆声", rumble_ratio * 100.0));
+            80.0
+        } else {
+            0.0
+        };
+        
+        let needs_denoise = estimated_snr_db > 0.0 && estimated_snr_db < 20.0;
+        if needs_denoise {
+            reasons.push(format!("估计信噪比 {:.1}dB 低于20dB，建议降噪", estimated_snr_db));
+        }
+        
+        // 动态范围：峰值因数过大时建议温和压缩
+        let crest_factor_db = if speech_rms_db > amplitude_to_db(0.0) { peak_dbfs - speech_rms_db } else { 0.0 };
+        let suggested_compressor = if crest_factor_db > 18.0 {
+            reasons.push(format!("峰值因数 {:.1}dB 超过18dB，建议 3:1 温和压缩使音量更平稳", crest_factor_db));
+            Some(CompressorSettings {
+                threshold: speech_rms_db + 6.0,
+                ratio: 3.0,
+                attack: 0.01,
+                release: 0.15,
+                makeup_gain: 0.0,
+                detector: 1,
+                auto_makeup: true,
+                auto_release: false,
+                oversample: 1,
+            })
+        } else {
+            None
+        };
+        
+        Ok(ProcessingSuggestion {
+            normalize_gain_db,
+            suggested_hpf_hz,
+            needs_denoise,
+            suggested_compressor,
+            loudness_lufs,
+            peak_dbfs,
+            noise_floor_db,
+            estimated_snr_db,
+            rumble_ratio,
+            crest_factor_db,
+            reasons,
+        })
+    }
+    
+    // 逐帧计算整段音频的频谱变化（新颖度曲线），用于在波形下方绘制起音/onset曲线
+    // 第 i 个值对应从 i * hop 个样本开始的1024点加汉宁窗帧与前一帧的差异，首帧为0；
+    // 计算方式由 set_flux_mode 决定（与实时的 spectral_flux_history 相同），但不影响实时处理的前一帧状态
+    #[wasm_bindgen]
+    pub fn spectral_flux_series(&self, audio_data: &[f32], hop: u32) -> Result<Box<[f32]>, JsValue> {
+        if hop == 0 {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "帧移必须大于0").into());
+        }
+        
+        let mut series = Vec::with_capacity(Self::frame_count(audio_data.len(), hop as usize));
+        let mut prev_magnitudes: Vec<f32> = Vec::new();
+        
+        self.for_each_spectrum_frame(audio_data, hop as usize, SPECTRUM_FFT_SIZE, |_, _, magnitudes| {
+            series.push(if prev_magnitudes.is_empty() {
+                0.0
+            } else {
+                spectral_flux(&prev_magnitudes, magnitudes, self.flux_mode)
+            });
+            prev_magnitudes.clear();
+            prev_magnitudes.extend_from_slice(magnitudes);
+        })?;
+        
+        Ok(series.into_boxed_slice())
+    }
+    
+    // 按用户绘制的EQ曲线处理音频：freqs_hz（Hz，严格递增且大于0）与 gains_db（dB）一一对应构成控制点，
+    // 控制点之间在对数频率上对dB值线性插值，低于第一个/高于最后一个控制点的频率沿用端点增益；
+    // 与 apply_linear_phase_eq 相同，在STFT域按实数增益缩放各频点（零相位），全部为0dB时输出与输入一致
+    #[wasm_bindgen]
+    pub fn apply_custom_curve(&self, audio_data: &[f32], freqs_hz: &[f32], gains_db: &[f32]) -> Result<Box<[f32]>, JsValue> {
+        if freqs_hz.len() != gains_db.len() {
+            return Err(ProcessorError::new(ErrorCode::LengthMismatch, "频率点与增益点数量不一致").into());
+        }
+        if freqs_hz.is_empty() {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "至少需要一个控制点").into());
+        }
+        if freqs_hz.iter().any(|f| !f.is_finite()) || freqs_hz[0] <= 0.0 || freqs_hz.windows(2).any(|pair| pair[1] <= pair[0]) {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "频率点必须大于0且严格递增").into());
+        }
+        
+        let fft_size = 4096;
+        let hop_size = fft_size / 4;
+        let last = freqs_hz.len() - 1;
+        
+        // 每个频点的线性增益
+        let gains: Vec<f32> = self
+            .bin_frequencies(fft_size)
+            .iter()
+            .map(|&freq| {
+                let gain_db = if freq <= freqs_hz[0] {
+                    gains_db[0]
+                } else if freq >= freqs_hz[last] {
+                    gains_db[last]
+                } else {
+                    // freqs_hz[i] < freq <= freqs_hz[i + 1]
+                    let i = freqs_hz.partition_point(|&f| f < freq) - 1;
+                    let t = (freq / freqs_hz[i]).ln() / (freqs_hz[i + 1] / freqs_hz[i]).ln();
+                    gains_db[i] + (gains_db[i + 1] - gains_db[i]) * t
+                };
+                db_to_amplitude(gain_db)
+            })
+            .collect();
+        
+        let mut output = audio_data.to_vec();
+        process_stft(&mut output, fft_size, hop_size, |spectrum| {
+            for (bin, &gain) in spectrum.iter_mut().zip(gains.iter()) {
+                *bin *= gain;
+            }
+        })?;
+        
+        Ok(output.into_boxed_slice())
+    }
+    
+    // G.711 μ-law 解码（8kHz 电话语料常用）：每字节一个采样，输出 -1~1 的浮点采样
+    #[wasm_bindgen]
+    pub fn decode_ulaw(&self, data: &[u8]) -> Box<[f32]> {
+        data.iter().map(|&byte| ulaw_to_linear(byte) as f32 / 32768.0).collect::<Vec<f32>>().into_boxed_slice()
+    }
+    
+    // G.711 μ-law 编码：输入按 16 位线性 PCM 量化后压扩为 8 位，超出 ±1 的采样会被限幅
+    #[wasm_bindgen]
+    pub fn encode_ulaw(&self, audio_data: &[f32]) -> Box<[u8]> {
+        audio_data.iter().map(|&sample| linear_to_ulaw(float_to_pcm16(sample))).collect::<Vec<u8>>().into_boxed_slice()
+    }
+    
+    // G.711 A-law 解码：每字节一个采样，输出 -1~1 的浮点采样
+    #[wasm_bindgen]
+    pub fn decode_alaw(&self, data: &[u8]) -> Box<[f32]> {
+        data.iter().map(|&byte| alaw_to_linear(byte) as f32 / 32768.0).collect::<Vec<f32>>().into_boxed_slice()
+    }
+    
+    // G.711 A-law 编码：输入按 16 位线性 PCM 量化后压扩为 8 位，超出 ±1 的采样会被限幅
+    #[wasm_bindgen]
+    pub fn encode_alaw(&self, audio_data: &[f32]) -> Box<[u8]> {
+        audio_data.iter().map(|&sample| linear_to_alaw(float_to_pcm16(sample))).collect::<Vec<u8>>().into_boxed_slice()
+    }
+    
+    // 估计混响时间 RT60（秒），判断房间录音是否混响过重而影响发音分析
+    // 输入为脉冲响应，或信号中一段门限截取的衰减区域（如拍手、单词结尾之后的余响）。
+    // 方法：从绝对值最大的采样（直达声）开始，用 Schroeder 反向积分得到能量衰减曲线
+    // EDC(t) = Σ_{τ≥t} h²(τ)，归一化为 dB 后对 -5~-25 dB 区间做最小二乘直线拟合（T20），
+    // 按斜率外推到 -60 dB；动态范围不足 25 dB 时退化为 -5~-15 dB（T10）。
+    // 积分前以末尾10%的平均功率作为底噪，并在10ms块功率降到底噪2倍以内处截断，避免底噪抬高曲线尾部。
+    // 没有可测量的衰减（过短、静音、能量不下降或动态范围不足 15 dB）时返回0
+    #[wasm_bindgen]
+    pub fn estimate_rt60(&self, impulse_or_decay: &[f32]) -> f32 {
+        let block_size = self.ms_to_samples(10.0);
+        let start = match impulse_or_decay
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+        {
+            Some((index, sample)) if *sample != 0.0 => index,
+            _ => return 0.0,
+        };
+        let decay = &impulse_or_decay[start..];
+        if decay.len() < block_size * 4 {
+            return 0.0;
+        }
+        
+        // 末尾10%作为底噪估计，在衰减落入底噪处截断
+        let tail = &decay[decay.len() - decay.len() / 10..];
+        let noise_power = tail.iter().map(|&s| (s * s) as f64).sum::<f64>() / tail.len() as f64;
+        let end = decay
+            .chunks(block_size)
+            .position(|block| {
+                block.iter().map(|&s| (s * s) as f64).sum::<f64>() / (block.len() as f64) <= noise_power * 2.0
+            })
+            .map_or(decay.len(), |block| (block * block_size).max(block_size));
+        let decay = &decay[..end];
+        
+        // Schroeder 反向积分
+        let mut edc = vec![0.0f64; decay.len()];
+        let mut energy = 0.0f64;
+        for (value, &sample) in edc.iter_mut().zip(decay.iter()).rev() {
+            energy += (sample * sample) as f64;
+            *value = energy;
+        }
+        let total = edc[0];
+        let edc_db: Vec<f64> = edc.iter().map(|&e| 10.0 * (e / total).max(1e-30).log10()).collect();
+        
+        // 按可用动态范围选择 T20 或 T10 的拟合区间
+        let min_db = edc_db.last().copied().unwrap_or(0.0);
+        let (upper_db, lower_db) = match [(-5.0, -25.0), (-5.0, -15.0)]
+            .into_iter()
+            .find(|&(_, lower)| min_db < lower)
+        {
+            Some(range) => range,
+            None => return 0.0,
+        };
+        
+        // 对区间内的 (时间, dB) 做最小二乘直线拟合
+        let (mut n, mut sum_t, mut sum_db, mut sum_tt, mut sum_tdb) = (0.0f64, 0.0f64, 0.0f64, 0.0f64, 0.0f64);
+        for (i, &db) in edc_db.iter().enumerate() {
+            if db <= upper_db && db >= lower_db {
+                let t = i as f64 / self.sample_rate as f64;
+                n += 1.0;
+                sum_t += t;
+                sum_db += db;
+                sum_tt += t * t;
+                sum_tdb += t * db;
+            }
+        }
+        let denominator = n * sum_tt - sum_t * sum_t;
+        if n < 2.0 || denominator <= 0.0 {
+            return 0.0;
+        }
+        let slope = (n * sum_tdb - sum_t * sum_db) / denominator;
+        
+        if slope < 0.0 { (-60.0 / slope) as f32 } else { 0.0 }
+    }
+    
+    // 单通道频谱去混响（晚期混响抑制），改善教室等混响环境录音的清晰度和音高检测
+    // 按指数衰减模型（Lebart 方法）估计晚期混响功率谱：P_late(k, m) = e^(-2·δ·T) · P(k, m - N)，
+    // 其中 δ = 3·ln10 / RT60（假定 RT60 为 DEREVERB_RT60_SEC），T 为 DEREVERB_DELAY_MS 对应的 N 帧延迟，
+    // P 为逐频点递归平滑的功率谱；每个频点的增益为 1 - strength · P_late / |X|²，最低 -20 dB，相位不变。
+    // strength 为0时不处理，1 为模型估计的全部晚期混响，最大2；越大尾音压得越干净，但越容易出现“音乐噪声”和语音发闷
+    #[wasm_bindgen]
+    pub fn dereverberate(&self, audio_data: &mut [f32], strength: f32) -> Result<(), JsValue> {
+        let fft_size = 2048;
+        let hop_size = fft_size / 4;
+        let strength = strength.clamp(0.0, 2.0);
+        if strength == 0.0 {
+            return Ok(());
+        }
+        if audio_data.len() < fft_size {
+            return Err(ProcessorError::new(ErrorCode::TooShort, "音频太短，无法进行去混响处理").into());
+        }
+        
+        let delay_frames = ((DEREVERB_DELAY_MS / 1000.0 * self.sample_rate as f32 / hop_size as f32).round() as usize).max(1);
+        let delay_sec = (delay_frames * hop_size) as f32 / self.sample_rate as f32;
+        let decay_rate = 3.0 * std::f32::consts::LN_10 / DEREVERB_RT60_SEC;
+        let late_factor = (-2.0 * decay_rate * delay_sec).exp();
+        let min_gain = db_to_amplitude(-20.0);
+        let power_smoothing = 0.5;
+        
+        // 最近 delay_frames 帧的平滑功率谱（环形缓冲），最旧的一帧即 P(k, m - N)
+        let mut smoothed_power: Vec<f32> = Vec::new();
+        let mut history: Vec<Vec<f32>> = Vec::with_capacity(delay_frames);
+        let mut frame_index = 0usize;
+        
+        process_stft(audio_data, fft_size, hop_size, |spectrum| {
+            if smoothed_power.is_empty() {
+                smoothed_power.resize(spectrum.len(), 0.0);
+            }
+            let slot = frame_index % delay_frames;
+            
+            for (k, bin) in spectrum.iter_mut().enumerate() {
+                let power = bin.norm_sqr();
+                smoothed_power[k] = power_smoothing * smoothed_power[k] + (1.0 - power_smoothing) * power;
+                
+                // 历史不足 N 帧时还没有可估计的晚期混响
+                if history.len() == delay_frames && power > 1e-20 {
+                    let late_power = late_factor * history[slot][k];
+                    let gain = (1.0 - strength * late_power / power).max(0.0).sqrt().max(min_gain);
+                    *bin *= gain;
+                }
+            }
+            
+            if history.len() < delay_frames {
+                history.push(smoothed_power.clone());
+            } else {
+                history[slot].copy_from_slice(&smoothed_power);
+            }
+            frame_index += 1;
+        })
+    }
+    
+    // 预测节拍时间（秒），用于节奏练习时按拍闪烁画面
+    // 假定速度大致恒定：以10ms帧移计算对数幅度谱的半波整流频谱变化作为起音包络，减去约0.5秒的局部均值；
+    // 在 40~200 BPM 范围内取包络去均值归一化自相关（按以120 BPM为中心的对数高斯权重抑制倍频/半频误判）的峰值作为节拍周期，
+    // 再选取使各拍位置包络之和最大的相位，之后逐拍外推一个周期并在 ±10% 周期内对齐到最近的起音峰，以跟踪轻微的速度漂移。
+    // 静音、短于两个最长节拍周期或节奏性不足（自相关峰值低于0.3）的输入返回空数组
+    #[wasm_bindgen]
+    pub fn predict_beats(&self, audio_data: &[f32]) -> Box<[f32]> {
+        let hop = self.ms_to_samples(10.0);
+        let frame_rate = self.sample_rate as f32 / hop as f32;
+        let min_period = (frame_rate * 60.0 / 200.0).floor().max(1.0) as usize;
+        let max_period = (frame_rate * 60.0 / 40.0).ceil() as usize;
+        if amplitude_to_db(self.calculate_rms(audio_data)) < self.silence_threshold_db(audio_data) {
+            return Box::new([]);
+        }
+        
+        // 起音包络：对数幅度谱的半波整流差分
+        let mut onset = Vec::with_capacity(Self::frame_count(audio_data.len(), hop));
+        let mut prev_log: Vec<f32> = Vec::new();
+        let mut current_log: Vec<f32> = Vec::new();
+        let analyzed = self.for_each_spectrum_frame(audio_data, hop, SPECTRUM_FFT_SIZE, |_, _, magnitudes| {
+            current_log.clear();
+            current_log.extend(magnitudes.iter().map(|&m| (1.0 + 100.0 * m).ln()));
+            onset.push(if prev_log.is_empty() {
+                0.0
+            } else {
+                spectral_flux(&prev_log, &current_log, FluxMode::Rectified)
+            });
+            std::mem::swap(&mut prev_log, &mut current_log);
+        });
+        if analyzed.is_err() || onset.len() < 2 * max_period {
+            return Box::new([]);
+        }
+        
+        // 减去局部均值并半波整流，只保留突出的起音
+        let half_window = (frame_rate * 0.25) as usize;
+        let mut prefix = vec![0.0f32; onset.len() + 1];
+        for (i, &value) in onset.iter().enumerate() {
+            prefix[i + 1] = prefix[i] + value;
+        }
+        let envelope: Vec<f32> = (0..onset.len())
+            .map(|i| {
+                let lo = i.saturating_sub(half_window);
+                let hi = (i + half_window + 1).min(onset.len());
+                (onset[i] - (prefix[hi] - prefix[lo]) / (hi - lo) as f32).max(0.0)
+            })
+            .collect();
+        
+        // 去均值后的归一化自相关（按重叠长度做无偏估计），包络非负，不去均值时噪声也会有很高的相关
+        let mean = envelope.iter().sum::<f32>() / envelope.len() as f32;
+        let centered: Vec<f32> = envelope.iter().map(|&v| v - mean).collect();
+        let variance = centered.iter().map(|&v| v * v).sum::<f32>() / centered.len() as f32;
+        if variance <= 1e-12 {
+            return Box::new([]);
+        }
+        let autocorr: Vec<f32> = (0..=max_period)
+            .map(|lag| {
+                let overlap = centered.len() - lag;
+                centered[..overlap].iter().zip(&centered[lag..]).map(|(a, b)| a * b).sum::<f32>() / overlap as f32 / variance
+            })
+            .collect();
+        
+        // 在速度范围内按权重选周期，节奏性按未加权的自相关判断
+        let tempo_weight = |lag: usize| {
+            let octaves = (frame_rate * 60.0 / lag as f32 / 120.0).log2();
+            (-0.5 * octaves * octaves).exp()
+        };
+        let period_frames = match (min_period..max_period)
+            .max_by(|&a, &b| (autocorr[a] * tempo_weight(a)).total_cmp(&(autocorr[b] * tempo_weight(b))))
+        {
+            Some(lag) if autocorr[lag] >= 0.3 => lag,
+            _ => return Box::new([]),
+        };
+        
+        // 抛物线插值得到小数周期
+        let (left, center, right) = (autocorr[period_frames - 1], autocorr[period_frames], autocorr[period_frames + 1]);
+        let curvature = left - 2.0 * center + right;
+        let period = if curvature < 0.0 {
+            period_frames as f32 + 0.5 * (left - right) / curvature
+        } else {
+            period_frames as f32
+        };
+        
+        // 相位：各拍位置包络之和最大的起点
+        let phase = (0..period_frames)
+            .max_by(|&a, &b| {
+                let score = |start: usize| {
+                    let mut sum = 0.0;
+                    let mut position = start as f32;
+                    while (position.round() as usize) < envelope.len() {
+                        sum += envelope[position.round() as usize];
+                        position += period;
+                    }
+                    sum
+                };
+                score(a).total_cmp(&score(b))
+            })
+            .unwrap_or(0);
+        
+        // 逐拍外推并对齐到附近的起音峰；帧的时间取分析窗中心
+        let tolerance = (period * 0.1).round() as usize;
+        let frame_offset_sec = SPECTRUM_FFT_SIZE as f32 / 2.0 / self.sample_rate as f32;
+        let mut beats = Vec::new();
+        let mut position = phase as f32;
+        while (position.round() as usize) < envelope.len() {
+            let predicted = position.round() as usize;
+            let lo = predicted.saturating_sub(tolerance);
+            let hi = (predicted + tolerance + 1).min(envelope.len());
+            let snapped = (lo..hi)
+                .max_by(|&a, &b| envelope[a].total_cmp(&envelope[b]))
+                .filter(|&i| envelope[i] > 0.0)
+                .map_or(position, |i| i as f32);
+            beats.push(snapped / frame_rate + frame_offset_sec);
+            position = snapped + period;
+        }
+        
+        beats.into_boxed_slice()
+    }
+    
+    // 频谱对比度（Spectral Contrast）：各子频带内峰值与谷值能量之差（dB），有助于区分音色相近的音素
+    // 子频带按倍频程划分：第0带为 0~200Hz，第 k 带为 200·2^(k-1) ~ 200·2^k Hz，最后一带延伸到奈奎斯特频率；
+    // num_bands 至少为1，且截断到奈奎斯特频率以下能容纳的频带数。
+    // 以2048点、帧移512的汉宁窗帧计算功率谱，带内最强2%频点（至少1个）的平均功率为峰值、最弱2%为谷值，
+    // 返回各非静音帧对比度的平均值（num_bands 个）；全部为静音时返回全0
+    #[wasm_bindgen]
+    pub fn spectral_contrast(&self, audio_data: &[f32], num_bands: u32) -> Result<Box<[f32]>, JsValue> {
+        let fft_size = 2048;
+        let hop = fft_size / 4;
+        let lowest_edge_hz = 200.0;
+        let nyquist = self.sample_rate as f32 / 2.0;
+        
+        // 倍频程频带边界（Hz）
+        let max_bands = 1 + (nyquist / lowest_edge_hz).log2().ceil().max(0.0) as usize;
+        let num_bands = (num_bands as usize).clamp(1, max_bands);
+        let mut edges_hz: Vec<f32> = (0..num_bands).map(|k| if k == 0 { 0.0 } else { lowest_edge_hz * 2f32.powi(k as i32 - 1) }).collect();
+        edges_hz.push(nyquist);
+        
+        let bin_width = self.sample_rate as f32 / fft_size as f32;
+        let num_bins = fft_size / 2 + 1;
+        let band_bins: Vec<(usize, usize)> = edges_hz
+            .windows(2)
+            .enumerate()
+            .map(|(k, pair)| {
+                let start = (pair[0] / bin_width).ceil() as usize;
+                // 最后一带包含奈奎斯特频点
+                let end = if k == num_bands - 1 { num_bins } else { (pair[1] / bin_width).ceil() as usize };
+                (start.min(num_bins), end.clamp(start.min(num_bins), num_bins))
+            })
+            .collect();
+        
+        let silence_db = self.silence_threshold_db(audio_data);
+        let mut sums = vec![0.0f64; num_bands];
+        let mut active_frames = 0usize;
+        let mut sorted: Vec<f32> = Vec::new();
+        
+        self.for_each_spectrum_frame(audio_data, hop, fft_size, |_, samples, magnitudes| {
+            if amplitude_to_db(self.calculate_rms(samples)) < silence_db {
+                return;
+            }
+            active_frames += 1;
+            
+            for (sum, &(start, end)) in sums.iter_mut().zip(band_bins.iter()) {
+                if end <= start {
+                    continue;
+                }
+                sorted.clear();
+                sorted.extend(magnitudes[start..end].iter().map(|&m| m * m));
+                sorted.sort_unstable_by(|a, b| a.total_cmp(b));
+                
+                let count = ((sorted.len() as f32 * 0.02).round() as usize).max(1);
+                let valley = sorted[..count].iter().sum::<f32>() / count as f32;
+                let peak = sorted[sorted.len() - count..].iter().sum::<f32>() / count as f32;
+                *sum += (10.0 * (peak.max(1e-10) / valley.max(1e-10)).log10()) as f64;
+            }
+        })?;
+        
+        Ok(sums
+            .iter()
+            .map(|&sum| if active_frames > 0 { (sum / active_frames as f64) as f32 } else { 0.0 })
+            .collect::<Vec<f32>>()
+            .into_boxed_slice())
+    }
+    
+    // 逐帧计算指定频带 [low_hz, high_hz] 内的能量（dB），可用于跟踪齿音、目标共振峰等频带随时间的变化
+    // 第 i 个值对应从 i * hop 开始的1024点汉宁窗帧，按窗能量归一化，满刻度正弦落在频带内时约为 -3 dB
+    // （与该正弦的RMS电平一致）；频带窄于一个频点时取离中心最近的频点。
+    // RMS电平低于静音阈值（见 set_auto_silence_threshold）的帧记为该阈值；要求 0 <= low_hz < high_hz <= 奈奎斯特频率
+    #[wasm_bindgen]
+    pub fn band_energy_series(&self, audio_data: &[f32], low_hz: f32, high_hz: f32, hop_ms: f32) -> Result<Box<[f32]>, JsValue> {
+        let nyquist = self.sample_rate as f32 / 2.0;
+        if !(low_hz >= 0.0 && low_hz < high_hz && high_hz <= nyquist) {
+            return Err(ProcessorError::new(ErrorCode::InvalidParameter, "频带范围无效，需满足 0 <= 下限 < 上限 <= 奈奎斯特频率").into());
+        }
+        
+        let fft_size = 1024;
+        let hop = self.ms_to_samples(hop_ms);
+        let bin_width = self.sample_rate as f32 / fft_size as f32;
+        let (first_bin, last_bin) = {
+            let first = (low_hz / bin_width).ceil() as usize;
+            let last = ((high_hz / bin_width).floor() as usize).min(fft_size / 2);
+            if first <= last {
+                (first, last)
+            } else {
+                let nearest = (((low_hz + high_hz) / 2.0 / bin_width).round() as usize).min(fft_size / 2);
+                (nearest, nearest)
+            }
+        };
+        
+        // 单边功率谱换算为均方值的系数：2 / (N · Σw²)，直流和奈奎斯特频点不加倍
+        let window_energy: f32 = WindowType::Hann.coefficients(fft_size).iter().map(|w| w * w).sum();
+        let scale = 1.0 / (fft_size as f32 * window_energy);
+        
+        let silence_db = self.silence_threshold_db(audio_data);
+        let mut series = Vec::with_capacity(Self::frame_count(audio_data.len(), hop));
+        
+        self.for_each_spectrum_frame(audio_data, hop, fft_size, |_, samples, magnitudes| {
+            if amplitude_to_db(self.calculate_rms(samples)) < silence_db {
+                series.push(silence_db);
+                return;
+            }
+            
+            let power: f32 = (first_bin..=last_bin)
+                .map(|k| {
+                    let factor = if k == 0 || k == fft_size / 2 { 1.0 } else { 2.0 };
+                    factor * magnitudes[k] * magnitudes[k] * scale
+                })
+                .sum();
+            series.push(10.0 * power.max(1e-10).log10());
+        })?;
+        
+        Ok(series.into_boxed_slice())
+    }
+}
+
+impl Default for AudioProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 工具函数：从 AudioBuffer 提取单声道数据
+#[wasm_bindgen]
+pub fn extract_mono_from_buffer(buffer: &AudioBuffer) -> Box<[f32]> {
+    let length = buffer.get_channel_data(0).unwrap().len();
+    let mut mono_data = vec![0.0; length];
+    
+    // 获取第一个通道数据
+    let channel_data = buffer.get_channel_data(0).unwrap();
+    
+    // 复制数据
+    mono_data.copy_from_slice(&channel_data);
+    
+    // 如果有多个通道，计算平均值
+    let num_channels = buffer.number_of_channels();
+    if num_channels > 1 {
+        for channel in 1..num_channels {
+            let channel_data = buffer.get_channel_data(channel).unwrap();
+            for i in 0..length {
+                mono_data[i] += channel_data[i];
+            }
+        }
+        
+        // 计算平均值
+        for sample in &mut mono_data {
+            *sample /= num_channels as f32;
+        }
+    }
+    
+    mono_data.into_boxed_slice()
+}
+
+// 初始化函数
+#[wasm_bindgen]
+pub fn init() {
+    init_panic_hook();
+    console::log_1(&"Audio Processor WASM module initialized".into());
+}
+
+// 通用的STFT处理：分帧加汉宁窗、FFT、由回调修改频谱、IFFT，再经 OverlapAdd 加窗叠加
+// 首尾帧用零填充，因此整段音频都能被重建
+fn process_stft<F>(audio_data: &mut [f32], fft_size: usize, hop_size: usize, mut modify: F) -> Result<(), JsValue>
+where
+    F: FnMut(&mut [Complex<f32>]),
+{
+    let len = audio_data.len();
+    if len == 0 {
+        return Ok(());
+    }
+    
+    let mut planner = RealFftPlanner::<f32>::new();
+    let r2c = planner.plan_fft_forward(fft_size);
+    let c2r = planner.plan_fft_inverse(fft_size);
+    
+    let mut buffer = vec![0.0; fft_size];
+    let mut spectrum = r2c.make_output_vec();
+    let mut output_buffer = vec![0.0; fft_size];
+    
+    let window = WindowType::Hann.coefficients(fft_size);
+    let mut overlap_add = OverlapAdd::new(window.clone(), len);
+    
+    // 第一帧从负偏移开始，使开头的样本也被完整覆盖
+    let mut frame_start = -((fft_size - hop_size) as isize);
+    while frame_start < len as isize {
+        // 复制并加窗
+        for j in 0..fft_size {
+            let idx = frame_start + j as isize;
+            buffer[j] = if idx >= 0 && (idx as usize) < len {
+                audio_data[idx as usize] * window[j]
+            } else {
+                0.0
+            };
+        }
+        
+        r2c.process(&mut buffer, &mut spectrum)
+            .map_err(|_| ProcessorError::new(ErrorCode::FftFailed, "FFT处理失败"))?;
+        
+        modify(&mut spectrum);
+        
+        // 实数信号的直流和奈奎斯特频点虚部必须为零
+        spectrum[0].im = 0.0;
+        if let Some(last) = spectrum.last_mut() {
+            last.im = 0.0;
+        }
+        
+        c2r.process(&mut spectrum, &mut output_buffer)
+            .map_err(|_| ProcessorError::new(ErrorCode::FftFailed, "IFFT处理失败"))?;
+        
+        // 逆变换未归一化，需除以 fft_size
+        overlap_add.add_frame(frame_start, &output_buffer, 1.0 / fft_size as f32);
+        
+        frame_start += hop_size as isize;
+    }
+    
+    audio_data.copy_from_slice(&overlap_add.finish());
+    
+    Ok(())
+}
+
+// 重叠相加（overlap-add）合成器
+// 每帧乘以合成窗后累加，同时记录每个样本累积的窗函数平方和，最后按该和归一化。
+// 稳态下该和就是COLA常数（如汉宁窗75%重叠时为1.5），首尾重叠不完整的样本也能正确还原
+struct OverlapAdd {
+    window: Vec<f32>,
+    output: Vec<f32>,
+    window_sum: Vec<f32>,
+}
+
+impl OverlapAdd {
+    fn new(window: Vec<f32>, len: usize) -> Self {
+        Self {
+            window,
+            output: vec![0.0; len],
+            window_sum: vec![0.0; len],
+        }
+    }
+    
+    // 把一帧（已乘以 scale）加窗后叠加到 frame_start 处，超出输出范围的部分被忽略
+    fn add_frame(&mut self, frame_start: isize, frame: &[f32], scale: f32) {
+        for (j, (&sample, &w)) in frame.iter().zip(self.window.iter()).enumerate() {
+            let idx = frame_start + j as isize;
+            if idx >= 0 && (idx as usize) < self.output.len() {
+                self.output[idx as usize] += sample * scale * w;
+                self.window_sum[idx as usize] += w * w;
+            }
+        }
+    }
+    
+    // 按累积的窗函数平方和归一化并返回结果
+    fn finish(self) -> Vec<f32> {
+        self.output
+            .iter()
+            .zip(self.window_sum.iter())
+            .map(|(&sample, &sum)| if sum > 1e-6 { sample / sum } else { 0.0 })
+            .collect()
+    }
+}
+
+// 返回构建信息 { crate_version, features_enabled, simd }，均在编译期确定
+#[wasm_bindgen]
+pub fn get_version_info() -> Result<JsValue, JsValue> {
+    let info = VersionInfo {
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        features_enabled: enabled_features().iter().map(|name| name.to_string()).collect(),
+        simd: cfg!(target_feature = "simd128"),
+    };
+    
+    Ok(serde_wasm_bindgen::to_value(&info)?)
+}
+
+// 编译时启用的可选 feature；在 Cargo.toml 中新增 feature 时在此按 cfg!(feature = "...") 补充
+fn enabled_features() -> Vec<&'static str> {
+    Vec::new()
+}
+
+// 计算给定窗函数和帧移下的COLA（恒定重叠相加）纹波：各帧窗函数按帧移叠加后的稳态和 Σw[n - k·hop]
+// 返回 (最大值 - 最小值) / 平均值，0 表示满足COLA条件（如汉宁窗在50%、75%重叠时）；
+// 定义与 scipy.signal.check_COLA 相同。OverlapAdd 按实际累积的窗平方和逐点归一化，因此重建本身不依赖此条件
+#[wasm_bindgen]
+pub fn verify_cola(window: WindowType, window_size: u32, hop: u32) -> f32 {
+    let window_size = window_size as usize;
+    let hop = hop as usize;
+    if window_size == 0 || hop == 0 {
+        return f32::INFINITY;
+    }
+    
+    let coefficients = window.coefficients(window_size);
+    
+    // 在一个帧移周期内累加所有重叠帧的窗函数
+    let sums: Vec<f32> = (0..hop)
+        .map(|n| (n..window_size).step_by(hop).map(|idx| coefficients[idx]).sum())
+        .collect();
+    
+    let max = sums.iter().cloned().fold(f32::MIN, f32::max);
+    let min = sums.iter().cloned().fold(f32::MAX, f32::min);
+    let mean = sums.iter().sum::<f32>() / hop as f32;
+    
+    if mean <= 0.0 {
+        return f32::INFINITY;
+    }
+    
+    (max - min) / mean
+}
+
+// 按指定方式计算两帧幅度谱之间的频谱变化
+fn spectral_flux(prev: &[f32], current: &[f32], mode: FluxMode) -> f32 {
+    // 限制到最小长度
+    let min_len = std::cmp::min(prev.len(), current.len());
+    let diffs = current[..min_len].iter().zip(prev[..min_len].iter()).map(|(c, p)| c - p);
+    
+    match mode {
+        // 只考虑增长的部分
+        FluxMode::Rectified => diffs.map(|diff| diff.max(0.0)).sum(),
+        FluxMode::L2 => diffs.map(|diff| diff * diff).sum::<f32>().sqrt(),
+        // 按频点序号加权（归一化到 0~1），越高的频率权重越大
+        FluxMode::Hfc => diffs
+            .enumerate()
+            .map(|(i, diff)| diff.max(0.0) * i as f32 / min_len as f32)
+            .sum(),
+    }
+}
+
+// 创建 ITU-R BS.1770 的K加权滤波器：高频搁架（约+4dB）和约38Hz的高通
+// 系数按任意采样率由模拟原型推导
+fn k_weighting_filters(sample_rate: f32) -> (IIRFilter, IIRFilter) {
+    // 第一级：高频搁架滤波器
+    let f0: f32 = 1681.9745;
+    let gain_db: f32 = 3.999_843_8;
+    let q: f32 = 0.707_175_24;
+    let k = (std::f32::consts::PI * hz_to_normalized(f0, sample_rate)).tan();
+    let vh = 10.0f32.powf(gain_db / 20.0);
+    let vb = vh.powf(0.499_666_78);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = IIRFilter::from_coefficients(
+        [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+    
+    // 第二级：高通滤波器
+    let f0: f32 = 38.135_47;
+    let q: f32 = 0.500_327_04;
+    let k = (std::f32::consts::PI * hz_to_normalized(f0, sample_rate)).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = IIRFilter::from_coefficients(
+        [1.0, -2.0, 1.0],
+        [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+    
+    (shelf, high_pass)
+}
+
+// 实时音高估计结果
+#[derive(Serialize, Deserialize)]
+pub struct PitchEstimate {
+    pub frequency: Option<f32>, // 清晰度不足时为空
+    pub clarity: f32,
+}
+
+// 攻击/释放包络跟随器，供压缩器等动态处理效果共用
+// 时间常数按 coef = exp(-1 / (t * sr)) 计算：时间 t 秒后包络走完阶跃的约63%，t 为0时立即跟随
+// detector：0 = 峰值（样本绝对值），1 = RMS（10ms滑动窗口）
+#[wasm_bindgen]
+pub struct EnvelopeFollower {
+    attack_coef: f32,
+    release_coef: f32,
+    envelope: f32,
+    rms_buffer: Vec<f32>,
+    rms_pos: usize,
+    rms_sum: f64,
+}
+
+#[wasm_bindgen]
+impl EnvelopeFollower {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32, attack: f32, release: f32, detector: u8) -> Self {
+        let rms_window = if detector == 1 { ((0.01 * sample_rate) as usize).max(1) } else { 0 };
+        
+        EnvelopeFollower {
+            attack_coef: time_constant_coef(attack, sample_rate),
+            release_coef: time_constant_coef(release, sample_rate),
+            envelope: 0.0,
+            rms_buffer: vec![0.0; rms_window],
+            rms_pos: 0,
+            rms_sum: 0.0,
         }
+    }
+    
+    // 处理单个样本，返回更新后的包络（线性振幅）
+    #[wasm_bindgen]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        // 计算当前样本电平
+        let input_level = if self.rms_buffer.is_empty() {
+            sample.abs()
+        } else {
+            let squared = sample * sample;
+            self.rms_sum += squared as f64 - self.rms_buffer[self.rms_pos] as f64;
+            self.rms_buffer[self.rms_pos] = squared;
+            self.rms_pos = (self.rms_pos + 1) % self.rms_buffer.len();
+            (self.rms_sum.max(0.0) / self.rms_buffer.len() as f64).sqrt() as f32
+        };
         
-        // 计算平均值
-        for sample in &mut mono_data {
-            *sample /= num_channels as f32;
+        // 上升时使用攻击系数，下降时使用释放系数
+        let coef = if input_level > self.envelope { self.attack_coef } else { self.release_coef };
+        self.envelope = coef * (self.envelope - input_level) + input_level;
+        
+        self.envelope
+    }
+    
+    // 当前包络值
+    #[wasm_bindgen]
+    pub fn envelope(&self) -> f32 {
+        self.envelope
+    }
+    
+    // 清空包络和RMS窗口
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+        self.rms_buffer.fill(0.0);
+        self.rms_pos = 0;
+        self.rms_sum = 0.0;
+    }
+}
+
+// 一阶平滑的时间常数系数 exp(-1 / (t * sr))，t <= 0 时返回0（立即跟随）
+fn time_constant_coef(time_sec: f32, sample_rate: f32) -> f32 {
+    if time_sec <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / (time_sec * sample_rate)).exp()
+}
+
+// 实时音高跟踪器：持有可复用的McLeod检测器和环形缓冲区，适合每帧（如60fps）调用
+// 分析窗口为1024个样本：44.1kHz下约23ms，可稳定检测约80Hz以上的音高，窗口越长低音越准但延迟越大；
+// 帧移为256个样本（约5.8ms）：每累计一个帧移的新数据才重新分析一次，
+// 且每次 push 最多只分析最新的一个窗口，因此单次调用的计算量和延迟都有上限
+#[wasm_bindgen]
+pub struct RealtimePitchTracker {
+    sample_rate: usize,
+    detector: McLeodDetector<f32>,
+    ring: Vec<f32>,
+    window: Vec<f32>,
+    write_pos: usize,
+    filled: usize,
+    pending: usize,
+    hop_size: usize,
+    frequency: Option<f32>,
+    clarity: f32,
+}
+
+#[wasm_bindgen]
+impl RealtimePitchTracker {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: usize) -> Self {
+        let window_size = 1024;
+        
+        RealtimePitchTracker {
+            sample_rate,
+            detector: McLeodDetector::new(window_size, window_size / 2),
+            ring: vec![0.0; window_size],
+            window: vec![0.0; window_size],
+            write_pos: 0,
+            filled: 0,
+            pending: 0,
+            hop_size: 256,
+            frequency: None,
+            clarity: 0.0,
+        }
+    }
+    
+    // 写入一帧音频并返回最新的音高估计 { frequency, clarity }
+    #[wasm_bindgen]
+    pub fn push(&mut self, frame: &[f32]) -> Result<JsValue, JsValue> {
+        let window_size = self.ring.len();
+        
+        // 只有最后一个窗口长度的数据会影响分析结果
+        let skip = frame.len().saturating_sub(window_size);
+        for &sample in &frame[skip..] {
+            self.ring[self.write_pos] = sample;
+            self.write_pos = (self.write_pos + 1) % window_size;
+        }
+        self.filled = (self.filled + frame.len()).min(window_size);
+        self.pending += frame.len();
+        
+        // 缓冲区已满且累计了足够的新数据时才重新分析
+        if self.filled == window_size && self.pending >= self.hop_size {
+            // 按时间顺序展开环形缓冲区
+            let (newer, older) = self.ring.split_at(self.write_pos);
+            self.window[..older.len()].copy_from_slice(older);
+            self.window[older.len()..].copy_from_slice(newer);
+            
+            match self.detector.get_pitch(&self.window, self.sample_rate, 0.2, 0.0) {
+                Some(pitch) => {
+                    self.clarity = pitch.clarity;
+                    self.frequency = if pitch.clarity >= 0.7 { Some(pitch.frequency) } else { None };
+                },
+                None => {
+                    self.clarity = 0.0;
+                    self.frequency = None;
+                },
+            }
+            
+            self.pending = 0;
+        }
+        
+        let estimate = PitchEstimate {
+            frequency: self.frequency,
+            clarity: self.clarity,
+        };
+        
+        Ok(serde_wasm_bindgen::to_value(&estimate)?)
+    }
+    
+    // 清空缓冲区和上一次的估计结果（切换输入或取消跟踪时调用）
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.ring.fill(0.0);
+        self.write_pos = 0;
+        self.filled = 0;
+        self.pending = 0;
+        self.frequency = None;
+        self.clarity = 0.0;
+    }
+}
+
+// 流式实时限幅器：跨多次 process 调用保持前瞻延迟线和增益包络，适合监听实时输入时保护听力
+// 输出比输入固定延迟 lookahead_ms（见 get_latency_samples）。每个样本所需的增益 min(1, 上限 / |x|)
+// 先取 lookahead + 1 个样本的滑动最小值，再做同长度的滑动平均，使增益在峰值到达输出前平滑降到位，
+// 因此任意分帧方式下输出都不会超过上限 ceiling_db（dBFS）；之后按 release_ms 的时间常数恢复
+#[wasm_bindgen]
+pub struct RealtimeLimiter {
+    ceiling: f32,
+    release_coef: f32,
+    delay_line: Vec<f32>,
+    delay_pos: usize,
+    min_window: VecDeque<(u64, f32)>,
+    smoothing: Vec<f32>,
+    smoothing_pos: usize,
+    smoothing_sum: f64,
+    sample_index: u64,
+    gain: f32,
+}
+
+#[wasm_bindgen]
+impl RealtimeLimiter {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32, ceiling_db: f32, lookahead_ms: f32, release_ms: f32) -> Self {
+        let lookahead = (lookahead_ms.max(0.0) * sample_rate / 1000.0).round() as usize;
+        
+        RealtimeLimiter {
+            ceiling: db_to_amplitude(ceiling_db),
+            release_coef: time_constant_coef(release_ms / 1000.0, sample_rate),
+            delay_line: vec![0.0; lookahead],
+            delay_pos: 0,
+            min_window: VecDeque::with_capacity(lookahead + 1),
+            smoothing: vec![1.0; lookahead + 1],
+            smoothing_pos: 0,
+            smoothing_sum: (lookahead + 1) as f64,
+            sample_index: 0,
+            gain: 1.0,
+        }
+    }
+    
+    // 原地处理一帧音频（任意长度），输出为 get_latency_samples 个样本之前的输入乘以限幅增益
+    #[wasm_bindgen]
+    pub fn process(&mut self, frame: &mut [f32]) {
+        let window = self.smoothing.len() as u64;
+        
+        for sample in frame.iter_mut() {
+            let input = *sample;
+            let required = if input.abs() > self.ceiling { self.ceiling / input.abs() } else { 1.0 };
+            
+            // 所需增益的滑动最小值（单调队列）
+            while self.min_window.back().is_some_and(|&(_, value)| value >= required) {
+                self.min_window.pop_back();
+            }
+            self.min_window.push_back((self.sample_index, required));
+            while self.min_window.front().is_some_and(|&(index, _)| index + window <= self.sample_index) {
+                self.min_window.pop_front();
+            }
+            let held = self.min_window.front().map_or(1.0, |&(_, value)| value);
+            
+            // 滑动平均：窗口内每个最小值都不大于即将输出样本所需的增益
+            self.smoothing_sum += held as f64 - self.smoothing[self.smoothing_pos] as f64;
+            self.smoothing[self.smoothing_pos] = held;
+            self.smoothing_pos = (self.smoothing_pos + 1) % self.smoothing.len();
+            let target = (self.smoothing_sum / window as f64) as f32;
+            
+            // 需要更多衰减时立即跟随，否则按释放时间恢复
+            self.gain = if target < self.gain {
+                target
+            } else {
+                self.release_coef * (self.gain - target) + target
+            };
+            
+            // 前瞻延迟
+            let delayed = if self.delay_line.is_empty() {
+                input
+            } else {
+                let delayed = self.delay_line[self.delay_pos];
+                self.delay_line[self.delay_pos] = input;
+                self.delay_pos = (self.delay_pos + 1) % self.delay_line.len();
+                delayed
+            };
+            
+            // 限幅到上限，避免浮点舍入造成的极小超出
+            *sample = (delayed * self.gain).clamp(-self.ceiling, self.ceiling);
+            self.sample_index += 1;
+        }
+    }
+    
+    // 前瞻造成的固定延迟（样本数）
+    #[wasm_bindgen]
+    pub fn get_latency_samples(&self) -> u32 {
+        self.delay_line.len() as u32
+    }
+    
+    // 当前增益衰减量（dB，0表示未限幅）
+    #[wasm_bindgen]
+    pub fn get_gain_reduction_db(&self) -> f32 {
+        -amplitude_to_db(self.gain)
+    }
+    
+    // 清空延迟线和增益包络（切换输入时调用）
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.delay_line.fill(0.0);
+        self.delay_pos = 0;
+        self.min_window.clear();
+        self.smoothing.fill(1.0);
+        self.smoothing_pos = 0;
+        self.smoothing_sum = self.smoothing.len() as f64;
+        self.sample_index = 0;
+        self.gain = 1.0;
+    }
+}
+
+// 简单的 xorshift32 伪随机数生成器（用于抖动和噪声生成，固定种子保证结果可复现）
+struct XorShiftRng {
+    state: u32,
+}
+
+impl XorShiftRng {
+    fn new(seed: u32) -> Self {
+        Self { state: seed.max(1) }
+    }
+    
+    // 返回 [0, 1) 区间的均匀分布随机数
+    fn next_f32(&mut self) -> f32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        (x >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+// 噪声发生器的固定种子（结果可复现，同样的参数总是生成同样的噪声）
+const NOISE_SEED: u32 = 0x9E37_79B9;
+
+// 生成白噪声（均匀分布），amplitude 为目标RMS电平（线性），便于按信噪比与语音混合
+#[wasm_bindgen]
+pub fn generate_white_noise(duration_sec: f32, sample_rate: u32, amplitude: f32) -> Box<[f32]> {
+    let num_samples = (duration_sec.max(0.0) * sample_rate as f32).round() as usize;
+    let mut rng = XorShiftRng::new(NOISE_SEED);
+    
+    // [-1, 1) 均匀分布的RMS为 1/√3
+    let scale = amplitude * 3.0f32.sqrt();
+    (0..num_samples)
+        .map(|_| (rng.next_f32() * 2.0 - 1.0) * scale)
+        .collect::<Vec<f32>>()
+        .into_boxed_slice()
+}
+
+// 生成粉红噪声（功率谱约 -3dB/倍频程），amplitude 为目标RMS电平（线性）
+// 使用 Paul Kellet 的白噪声滤波法（七个一阶滤波器并联），生成后按实际RMS精确缩放
+#[wasm_bindgen]
+pub fn generate_pink_noise(duration_sec: f32, sample_rate: u32, amplitude: f32) -> Box<[f32]> {
+    let num_samples = (duration_sec.max(0.0) * sample_rate as f32).round() as usize;
+    let mut rng = XorShiftRng::new(NOISE_SEED);
+    let mut b = [0.0f32; 7];
+    
+    let mut next_pink = || {
+        let white = rng.next_f32() * 2.0 - 1.0;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.153852;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b.iter().sum::<f32>() + white * 0.5362;
+        b[6] = white * 0.115926;
+        pink
+    };
+    
+    // 预热滤波器状态，避免开头的低频建立过程
+    for _ in 0..4096 {
+        next_pink();
+    }
+    
+    let mut output: Vec<f32> = (0..num_samples).map(|_| next_pink()).collect();
+    
+    let sum_squares: f64 = output.iter().map(|&x| (x * x) as f64).sum();
+    if sum_squares > 0.0 {
+        let scale = amplitude / (sum_squares / num_samples as f64).sqrt() as f32;
+        output.iter_mut().for_each(|x| *x *= scale);
+    }
+    
+    output.into_boxed_slice()
+}
+
+// 将K加权信号的均方值换算为响度（LUFS），静音时下限约为 -120 LUFS
+fn mean_square_to_lufs(power: f32) -> f32 {
+    -0.691 + 10.0 * power.max(1e-12).log10()
+}
+
+// 用自相关法和 Levinson-Durbin 递推计算LPC系数，返回 [1, a1, ..., a_order]
+fn lpc_coefficients(frame: &[f32], order: usize) -> Option<Vec<f32>> {
+    if frame.len() <= order {
+        return None;
+    }
+    
+    // 自相关
+    let autocorr: Vec<f32> = (0..=order)
+        .map(|lag| frame[lag..].iter().zip(frame.iter()).map(|(a, b)| a * b).sum())
+        .collect();
+    if autocorr[0] <= 1e-10 {
+        return None;
+    }
+    
+    let mut coefficients = vec![0.0f32; order + 1];
+    coefficients[0] = 1.0;
+    let mut error = autocorr[0];
+    
+    for i in 1..=order {
+        let mut acc = autocorr[i];
+        for j in 1..i {
+            acc += coefficients[j] * autocorr[i - j];
+        }
+        let reflection = -acc / error;
+        
+        let previous = coefficients.clone();
+        for j in 1..i {
+            coefficients[j] = previous[j] + reflection * previous[i - j];
+        }
+        coefficients[i] = reflection;
+        
+        error *= 1.0 - reflection * reflection;
+        if error <= 0.0 {
+            return None;
+        }
+    }
+    
+    Some(coefficients)
+}
+
+// Hz 转换为 Bark 尺度（Traunmüller 公式）
+fn hz_to_bark(freq: f32) -> f32 {
+    26.81 * freq / (1960.0 + freq) - 0.53
+}
+
+// 基于自相关的周期性分析器（FFT计算自相关），复用FFT计划和缓冲区
+// 在 60~500Hz 的基频范围内计算按重叠部分能量归一化的自相关，最大值接近1表示强周期性（浊音），
+// 噪声通常低于0.3；不受McLeod窗口衰减影响，低音男声也能稳定判断
+struct PeriodicityAnalyzer {
+    r2c: Arc<dyn RealToComplex<f32>>,
+    c2r: Arc<dyn ComplexToReal<f32>>,
+    buffer: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    autocorr: Vec<f32>,
+}
+
+impl PeriodicityAnalyzer {
+    fn new(frame_size: usize) -> Self {
+        let fft_size = (2 * frame_size).next_power_of_two();
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(fft_size);
+        let c2r = planner.plan_fft_inverse(fft_size);
+        let spectrum = r2c.make_output_vec();
+        
+        Self {
+            r2c,
+            c2r,
+            buffer: vec![0.0; fft_size],
+            spectrum,
+            autocorr: vec![0.0; fft_size],
+        }
+    }
+    
+    // 返回 (周期性强度 0~1, 对应基频Hz)；帧太短或无能量时返回 (0, 0)
+    fn analyze(&mut self, frame: &[f32], sample_rate: usize) -> (f32, f32) {
+        let n = frame.len().min(self.buffer.len() / 2);
+        let min_lag = (sample_rate / 500).max(1);
+        let max_lag = (sample_rate / 60).min(n / 2);
+        if n == 0 || min_lag >= max_lag {
+            return (0.0, 0.0);
+        }
+        
+        // 补零后计算功率谱，再逆变换得到自相关
+        self.buffer[..n].copy_from_slice(&frame[..n]);
+        self.buffer[n..].fill(0.0);
+        if self.r2c.process(&mut self.buffer, &mut self.spectrum).is_err() {
+            return (0.0, 0.0);
+        }
+        for bin in self.spectrum.iter_mut() {
+            *bin = Complex::new(bin.re * bin.re + bin.im * bin.im, 0.0);
+        }
+        if self.c2r.process(&mut self.spectrum, &mut self.autocorr).is_err() {
+            return (0.0, 0.0);
+        }
+        
+        // 重叠部分能量的前缀和
+        let mut prefix = vec![0.0f32; n + 1];
+        for i in 0..n {
+            prefix[i + 1] = prefix[i] + frame[i] * frame[i];
+        }
+        if prefix[n] <= 1e-10 {
+            return (0.0, 0.0);
+        }
+        
+        let scale = self.buffer.len() as f32;
+        let normalized: Vec<f32> = (min_lag..=max_lag)
+            .map(|lag| {
+                let head = prefix[n - lag];
+                let tail = prefix[n] - prefix[lag];
+                let denom = (head * tail).sqrt();
+                if denom > 1e-10 { self.autocorr[lag] / scale / denom } else { 0.0 }
+            })
+            .collect();
+        
+        let strength = normalized.iter().cloned().fold(0.0, f32::max);
+        if strength <= 0.0 {
+            return (0.0, 0.0);
+        }
+        
+        // 取达到最大值90%的最短周期所在的峰（避免倍周期误判），再爬升到该峰的顶点
+        let mut offset = normalized.iter().position(|&value| value >= 0.9 * strength).unwrap_or(0);
+        while offset + 1 < normalized.len() && normalized[offset + 1] > normalized[offset] {
+            offset += 1;
+        }
+        let lag = min_lag + offset;
+        
+        (strength.min(1.0), sample_rate as f32 / lag as f32)
+    }
+}
+
+// 幅度谱的质心（Hz），bin_width 为相邻频点的间隔；幅度全为0时返回0
+fn spectral_centroid(magnitudes: &[f32], bin_width: f32) -> f32 {
+    let magnitude_sum: f32 = magnitudes.iter().sum();
+    if magnitude_sum <= 0.0 {
+        return 0.0;
+    }
+    
+    let weighted_sum: f32 = magnitudes.iter().enumerate().map(|(k, m)| k as f32 * bin_width * m).sum();
+    weighted_sum / magnitude_sum
+}
+
+// dB频谱中 bin 周围 ±half_span 个频点（不含 bin 本身）的平均电平
+fn local_mean(spectrum_db: &[f32], bin: usize, half_span: usize) -> f32 {
+    let lo = bin.saturating_sub(half_span);
+    let hi = (bin + half_span + 1).min(spectrum_db.len());
+    let count = hi - lo - 1;
+    if count == 0 {
+        return spectrum_db[bin];
+    }
+    (spectrum_db[lo..hi].iter().sum::<f32>() - spectrum_db[bin]) / count as f32
+}
+
+// 找出dB频谱中的局部峰：比相邻频点都高，且比周围 ±half_span 个频点的平均电平高出至少 min_prominence_db
+fn find_prominent_peaks(spectrum_db: &[f32], half_span: usize, min_prominence_db: f32) -> Vec<usize> {
+    (1..spectrum_db.len().saturating_sub(1))
+        .filter(|&bin| {
+            let value = spectrum_db[bin];
+            value > spectrum_db[bin - 1]
+                && value >= spectrum_db[bin + 1]
+                && value - local_mean(spectrum_db, bin, half_span) >= min_prominence_db
+        })
+        .collect()
+}
+
+// 用FFT计算 target 相对 reference 的最佳延迟（±max_lag 个样本内）
+// 返回 (延迟, 归一化互相关峰值)：延迟为正表示 target 滞后，即 target[n] ≈ reference[n - 延迟]；
+// 任一信号能量为0时返回 None
+fn cross_correlation_lag(reference: &[f32], target: &[f32], max_lag: usize) -> Option<(isize, f32)> {
+    let energy_reference: f64 = reference.iter().map(|&x| (x * x) as f64).sum();
+    let energy_target: f64 = target.iter().map(|&x| (x * x) as f64).sum();
+    if energy_reference <= 0.0 || energy_target <= 0.0 {
+        return None;
+    }
+    
+    let fft_size = (reference.len() + target.len()).next_power_of_two();
+    let mut planner = RealFftPlanner::<f32>::new();
+    let r2c = planner.plan_fft_forward(fft_size);
+    let c2r = planner.plan_fft_inverse(fft_size);
+    
+    let mut buffer = vec![0.0; fft_size];
+    buffer[..reference.len()].copy_from_slice(reference);
+    let mut reference_spectrum = r2c.make_output_vec();
+    r2c.process(&mut buffer, &mut reference_spectrum).ok()?;
+    
+    buffer.fill(0.0);
+    buffer[..target.len()].copy_from_slice(target);
+    let mut target_spectrum = r2c.make_output_vec();
+    r2c.process(&mut buffer, &mut target_spectrum).ok()?;
+    
+    // 互相关 c[k] = Σ reference[n] * target[n + k]，负延迟位于缓冲区末尾
+    for (t, r) in target_spectrum.iter_mut().zip(reference_spectrum.iter()) {
+        *t *= r.conj();
+    }
+    // 实数逆变换要求直流和奈奎斯特频点的虚部为0
+    target_spectrum[0].im = 0.0;
+    if let Some(last) = target_spectrum.last_mut() {
+        last.im = 0.0;
+    }
+    c2r.process(&mut target_spectrum, &mut buffer).ok()?;
+    
+    let max_positive = max_lag.min(target.len().saturating_sub(1));
+    let max_negative = max_lag.min(reference.len().saturating_sub(1));
+    let (lag, value) = (0..=max_positive)
+        .map(|k| (k as isize, buffer[k]))
+        .chain((1..=max_negative).map(|k| (-(k as isize), buffer[fft_size - k])))
+        .fold((0, f32::MIN), |best, candidate| if candidate.1 > best.1 { candidate } else { best });
+    
+    let correlation = value as f64 / fft_size as f64 / (energy_reference * energy_target).sqrt();
+    Some((lag, correlation as f32))
+}
+
+// Blackman窗加权的sinc核：x 为sinc自变量（样本），position 为在窗内的相对位置（-1~1）
+fn windowed_sinc(x: f64, position: f64) -> f64 {
+    if position.abs() >= 1.0 {
+        return 0.0;
+    }
+    
+    let sinc = if x.abs() < 1e-9 {
+        1.0
+    } else {
+        (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x)
+    };
+    let window = 0.42
+        + 0.5 * (std::f64::consts::PI * position).cos()
+        + 0.08 * (2.0 * std::f64::consts::PI * position).cos();
+    
+    sinc * window
+}
+
+// 加窗sinc插值重采样：第 n 个输出样本取输入位置 n * step 处的插值，共 output_len 个
+// step > 1（降采样）时sinc截止频率降为 1/step 以防混叠；按权重和归一化，保证直流增益为1（边界处也不衰减）
+fn sinc_resample(audio_data: &[f32], step: f64, output_len: usize) -> Vec<f32> {
+    let cutoff = (1.0 / step).min(1.0);
+    let half_width = (16.0 / cutoff).ceil() as isize;
+    
+    (0..output_len)
+        .map(|n| {
+            let position = n as f64 * step;
+            let center = position.floor() as isize;
+            let mut sum = 0.0;
+            let mut weight_sum = 0.0;
+            
+            for k in (center - half_width + 1)..=(center + half_width) {
+                if k < 0 || k as usize >= audio_data.len() {
+                    continue;
+                }
+                let distance = position - k as f64;
+                let weight = windowed_sinc(distance * cutoff, distance / half_width as f64);
+                sum += audio_data[k as usize] as f64 * weight;
+                weight_sum += weight;
+            }
+            
+            if weight_sum.abs() > 1e-9 { (sum / weight_sum) as f32 } else { 0.0 }
+        })
+        .collect()
+}
+
+// 超过该阶数时FIR卷积改用FFT分块计算
+const FIR_FFT_THRESHOLD: usize = 64;
+
+// FIR因果卷积，输出截断为输入长度
+fn fir_filter(audio_data: &mut [f32], coefficients: &[f32]) -> Result<(), JsValue> {
+    let len = audio_data.len();
+    let taps = coefficients.len();
+    if len == 0 || taps == 0 {
+        return Ok(());
+    }
+    
+    if taps < FIR_FFT_THRESHOLD {
+        // 从后往前计算，每个输出只依赖尚未被覆盖的更早样本
+        for n in (0..len).rev() {
+            let mut acc = 0.0;
+            for (k, &h) in coefficients.iter().enumerate().take(n + 1) {
+                acc += h * audio_data[n - k];
+            }
+            audio_data[n] = acc;
         }
+        return Ok(());
+    }
+    
+    // 重叠相加：每块 block_size 个输入与滤波器的线性卷积长度不超过 fft_size
+    let fft_size = (2 * taps).next_power_of_two();
+    let block_size = fft_size - taps + 1;
+    
+    let mut planner = RealFftPlanner::<f32>::new();
+    let r2c = planner.plan_fft_forward(fft_size);
+    let c2r = planner.plan_fft_inverse(fft_size);
+    
+    let mut buffer = vec![0.0; fft_size];
+    buffer[..taps].copy_from_slice(coefficients);
+    let mut kernel_spectrum = r2c.make_output_vec();
+    r2c.process(&mut buffer, &mut kernel_spectrum)
+        .map_err(|_| ProcessorError::new(ErrorCode::FftFailed, "FFT处理失败"))?;
+    
+    let mut spectrum = r2c.make_output_vec();
+    let mut output_buffer = vec![0.0; fft_size];
+    let mut output = vec![0.0; len];
+    let scale = 1.0 / fft_size as f32;
+    
+    for block_start in (0..len).step_by(block_size) {
+        let block_end = (block_start + block_size).min(len);
+        buffer.fill(0.0);
+        buffer[..block_end - block_start].copy_from_slice(&audio_data[block_start..block_end]);
+        
+        r2c.process(&mut buffer, &mut spectrum)
+            .map_err(|_| ProcessorError::new(ErrorCode::FftFailed, "FFT处理失败"))?;
+        for (bin, kernel) in spectrum.iter_mut().zip(kernel_spectrum.iter()) {
+            *bin *= kernel;
+        }
+        c2r.process(&mut spectrum, &mut output_buffer)
+            .map_err(|_| ProcessorError::new(ErrorCode::FftFailed, "IFFT处理失败"))?;
+        
+        let valid = fft_size.min(len - block_start);
+        for (dst, &sample) in output[block_start..block_start + valid].iter_mut().zip(output_buffer.iter()) {
+            *dst += sample * scale;
+        }
+    }
+    
+    audio_data.copy_from_slice(&output);
+    
+    Ok(())
+}
+
+// 浮点采样转换为 16 位线性 PCM（四舍五入并限幅）
+fn float_to_pcm16(sample: f32) -> i16 {
+    (sample * 32768.0).round().clamp(-32768.0, 32767.0) as i16
+}
+
+// G.711 μ-law 压扩常量：偏置 0x84，线性幅度上限 32635
+const ULAW_BIAS: i32 = 0x84;
+const ULAW_CLIP: i32 = 32635;
+
+// 16 位线性 PCM -> μ-law 字节（ITU-T G.711）
+fn linear_to_ulaw(pcm: i16) -> u8 {
+    let pcm = pcm as i32;
+    let sign = if pcm < 0 { 0x80 } else { 0x00 };
+    let magnitude = pcm.abs().min(ULAW_CLIP) + ULAW_BIAS;
+    
+    // 段号 = 最高有效位位置 - 7（0~7），段内取最高位之后的4位
+    let exponent = (31 - magnitude.leading_zeros() as i32 - 7).max(0);
+    let mantissa = (magnitude >> (exponent + 3)) & 0x0F;
+    
+    !((sign | (exponent << 4) | mantissa) as u8)
+}
+
+// μ-law 字节 -> 16 位线性 PCM（ITU-T G.711）
+fn ulaw_to_linear(byte: u8) -> i16 {
+    let byte = !byte as i32;
+    let exponent = (byte & 0x70) >> 4;
+    let magnitude = ((((byte & 0x0F) << 3) + ULAW_BIAS) << exponent) - ULAW_BIAS;
+    
+    if byte & 0x80 != 0 { -magnitude as i16 } else { magnitude as i16 }
+}
+
+// 16 位线性 PCM -> A-law 字节（ITU-T G.711，内部按13位精度量化，偶数位与 0x55 异或）
+fn linear_to_alaw(pcm: i16) -> u8 {
+    let pcm = pcm as i32 >> 3;
+    let (mask, magnitude) = if pcm >= 0 { (0xD5, pcm) } else { (0x55, -pcm - 1) };
+    
+    // 段号：第0段覆盖 0~31，之后每段范围翻倍
+    let segment = (32 - (magnitude >> 5).leading_zeros()) as i32;
+    if segment >= 8 {
+        return (0x7F ^ mask) as u8;
     }
+    let mantissa = if segment < 2 { (magnitude >> 1) & 0x0F } else { (magnitude >> segment) & 0x0F };
     
-    mono_data.into_boxed_slice()
+    (((segment << 4) | mantissa) ^ mask) as u8
 }
 
-// 初始化函数
-#[wasm_bindgen]
-pub fn init() {
-    init_panic_hook();
-    console::log_1(&"Audio Processor WASM module initialized".into());
+// A-law 字节 -> 16 位线性 PCM（ITU-T G.711）
+fn alaw_to_linear(byte: u8) -> i16 {
+    let byte = (byte ^ 0x55) as i32;
+    let segment = (byte & 0x70) >> 4;
+    let mut magnitude = (byte & 0x0F) << 4;
+    match segment {
+        0 => magnitude += 8,
+        1 => magnitude += 0x108,
+        _ => magnitude = (magnitude + 0x108) << (segment - 1),
+    }
+    
+    if byte & 0x80 != 0 { magnitude as i16 } else { -magnitude as i16 }
 }
 
-// 添加一个简单的IIR滤波器结构
+// 添加一个简单的IIR滤波器结构（RBJ双二阶）
+// 所有构造函数的频率参数单位均为Hz，并显式传入采样率；频率必须在 0 到奈奎斯特频率之间
 struct IIRFilter {
     a: [f32; 3], // 分母系数
     b: [f32; 3], // 分子系数
@@ -649,10 +5255,12 @@ struct IIRFilter {
 }
 
 impl IIRFilter {
-    // 创建一个低通滤波器
-    fn low_pass(cutoff: f32, q: f32) -> Self {
+    // 创建一个低通滤波器（cutoff_hz 为 -3dB 截止频率，q = 0.707 时为巴特沃斯响应）
+    fn low_pass(cutoff_hz: f32, sample_rate: f32, q: f32) -> Self {
+        debug_assert!(cutoff_hz > 0.0 && cutoff_hz < sample_rate / 2.0);
+        
         // 计算滤波器系数
-        let omega = 2.0 * std::f32::consts::PI * cutoff;
+        let omega = 2.0 * std::f32::consts::PI * hz_to_normalized(cutoff_hz, sample_rate);
         let alpha = omega.sin() / (2.0 * q);
         
         let b0 = (1.0 - omega.cos()) / 2.0;
@@ -674,10 +5282,12 @@ impl IIRFilter {
         }
     }
     
-    // 创建一个高通滤波器
-    fn high_pass(cutoff: f32, q: f32) -> Self {
+    // 创建一个高通滤波器（cutoff_hz 为 -3dB 截止频率，q = 0.707 时为巴特沃斯响应）
+    fn high_pass(cutoff_hz: f32, sample_rate: f32, q: f32) -> Self {
+        debug_assert!(cutoff_hz > 0.0 && cutoff_hz < sample_rate / 2.0);
+        
         // 计算滤波器系数
-        let omega = 2.0 * std::f32::consts::PI * cutoff;
+        let omega = 2.0 * std::f32::consts::PI * hz_to_normalized(cutoff_hz, sample_rate);
         let alpha = omega.sin() / (2.0 * q);
         
         let b0 = (1.0 + omega.cos()) / 2.0;
@@ -699,10 +5309,12 @@ impl IIRFilter {
         }
     }
     
-    // 创建一个峰值滤波器 (band peak)
-    fn peak(cutoff: f32, q: f32, gain: f32) -> Self {
+    // 创建一个峰值滤波器 (band peak)，center_hz 为中心频率，gain 单位为dB
+    fn peak(center_hz: f32, sample_rate: f32, q: f32, gain: f32) -> Self {
+        debug_assert!(center_hz > 0.0 && center_hz < sample_rate / 2.0);
+        
         // 计算滤波器系数
-        let omega = 2.0 * std::f32::consts::PI * cutoff;
+        let omega = 2.0 * std::f32::consts::PI * hz_to_normalized(center_hz, sample_rate);
         let alpha = omega.sin() / (2.0 * q);
         let a = 10.0f32.powf(gain / 40.0); // 将dB增益转换为线性增益
         
@@ -725,6 +5337,29 @@ impl IIRFilter {
         }
     }
     
+    // 直接由归一化后的系数创建滤波器（a[0] 应为1）
+    fn from_coefficients(b: [f32; 3], a: [f32; 3]) -> Self {
+        Self {
+            a,
+            b,
+            x: [0.0; 3],
+            y: [0.0; 3],
+        }
+    }
+    
+    // 计算滤波器在 freq_hz 处的复频率响应 H(e^jω)
+    fn response(&self, freq_hz: f32, sample_rate: f32) -> Complex<f32> {
+        let omega = 2.0 * std::f32::consts::PI * hz_to_normalized(freq_hz, sample_rate);
+        // z^-1 和 z^-2
+        let z1 = Complex::new(omega.cos(), -omega.sin());
+        let z2 = z1 * z1;
+        
+        let numerator = Complex::new(self.b[0], 0.0) + z1 * self.b[1] + z2 * self.b[2];
+        let denominator = Complex::new(self.a[0], 0.0) + z1 * self.a[1] + z2 * self.a[2];
+        
+        numerator / denominator
+    }
+    
     // 处理单个样本
     fn process(&mut self, input: f32) -> f32 {
         // 更新输入历史
@@ -746,4 +5381,820 @@ impl IIRFilter {
         
         output
     }
-} 
\ No newline at end of file
+}
+
+// f64精度的双二阶滤波器，供离线高精度渲染使用（系数公式与 IIRFilter 相同，频率参数同样为Hz加采样率）
+struct BiquadFilterF64 {
+    a: [f64; 3], // 分母系数
+    b: [f64; 3], // 分子系数
+    x: [f64; 3], // 输入历史
+    y: [f64; 3], // 输出历史
+}
+
+impl BiquadFilterF64 {
+    // 创建一个低通滤波器（cutoff_hz 为 -3dB 截止频率）
+    fn low_pass(cutoff_hz: f64, sample_rate: f64, q: f64) -> Self {
+        debug_assert!(cutoff_hz > 0.0 && cutoff_hz < sample_rate / 2.0);
+        
+        let omega = 2.0 * std::f64::consts::PI * hz_to_normalized(cutoff_hz, sample_rate);
+        let alpha = omega.sin() / (2.0 * q);
+        let cos_omega = omega.cos();
+        let a0 = 1.0 + alpha;
+        
+        Self::from_coefficients(
+            [(1.0 - cos_omega) / 2.0 / a0, (1.0 - cos_omega) / a0, (1.0 - cos_omega) / 2.0 / a0],
+            [1.0, -2.0 * cos_omega / a0, (1.0 - alpha) / a0],
+        )
+    }
+    
+    // 创建一个高通滤波器（cutoff_hz 为 -3dB 截止频率）
+    fn high_pass(cutoff_hz: f64, sample_rate: f64, q: f64) -> Self {
+        debug_assert!(cutoff_hz > 0.0 && cutoff_hz < sample_rate / 2.0);
+        
+        let omega = 2.0 * std::f64::consts::PI * hz_to_normalized(cutoff_hz, sample_rate);
+        let alpha = omega.sin() / (2.0 * q);
+        let cos_omega = omega.cos();
+        let a0 = 1.0 + alpha;
+        
+        Self::from_coefficients(
+            [(1.0 + cos_omega) / 2.0 / a0, -(1.0 + cos_omega) / a0, (1.0 + cos_omega) / 2.0 / a0],
+            [1.0, -2.0 * cos_omega / a0, (1.0 - alpha) / a0],
+        )
+    }
+    
+    // 直接由归一化后的系数创建滤波器（a[0] 应为1）
+    fn from_coefficients(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            a,
+            b,
+            x: [0.0; 3],
+            y: [0.0; 3],
+        }
+    }
+    
+    // 处理单个样本
+    fn process(&mut self, input: f64) -> f64 {
+        self.x[2] = self.x[1];
+        self.x[1] = self.x[0];
+        self.x[0] = input;
+        
+        let output = self.b[0] * self.x[0] + 
+                     self.b[1] * self.x[1] + 
+                     self.b[2] * self.x[2] - 
+                     self.a[1] * self.y[0] - 
+                     self.a[2] * self.y[1];
+        
+        self.y[2] = self.y[1];
+        self.y[1] = self.y[0];
+        self.y[0] = output;
+        
+        output
+    }
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const SAMPLE_RATE: f32 = 44100.0;
+    
+    fn sine(freq_hz: f32, amplitude: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * freq_hz * i as f32 / SAMPLE_RATE).sin())
+            .collect()
+    }
+    
+    fn white_noise(amplitude: f32, len: usize, seed: u32) -> Vec<f32> {
+        let mut rng = XorShiftRng::new(seed);
+        (0..len).map(|_| (rng.next_f32() - 0.5) * 2.0 * amplitude).collect()
+    }
+    
+    #[test]
+    fn remove_clicks_repairs_spike_but_keeps_plosive() {
+        let mut audio = sine(200.0, 0.1, 22050);
+        let clean = audio.clone();
+        let click = 8000;
+        audio[click] += 0.8;
+        
+        // 约20ms后的爆破音：起音陡峭、按5ms时间常数衰减的噪声
+        let plosive_start = click + 882;
+        let plosive_len = 882;
+        let burst = white_noise(0.5, plosive_len, 23);
+        for (j, value) in burst.iter().enumerate() {
+            audio[plosive_start + j] += value * (-(j as f32) / (0.005 * SAMPLE_RATE)).exp();
+        }
+        let original = audio.clone();
+        
+        AudioProcessor::new().remove_clicks(&mut audio, 0.5);
+        
+        assert!((audio[click] - clean[click]).abs() < 0.05, "click left at {}", audio[click]);
+        let plosive = plosive_start..plosive_start + plosive_len;
+        assert_eq!(audio[plosive.clone()], original[plosive]);
+    }
+    
+    fn compressor_settings(threshold_db: f32, ratio: f32, attack: f32, release: f32) -> CompressorSettings {
+        CompressorSettings {
+            threshold: threshold_db,
+            ratio,
+            attack,
+            release,
+            makeup_gain: 0.0,
+            detector: 0,
+            auto_makeup: false,
+            auto_release: false,
+            oversample: 1,
+        }
+    }
+    
+    // 逐样本增益轨迹（输出 / 输入），跳过过零附近的样本
+    fn gain_trajectory(input: &[f32], output: &[f32]) -> Vec<f32> {
+        input
+            .iter()
+            .zip(output)
+            .filter(|(x, _)| x.abs() > 0.05)
+            .map(|(x, y)| y / x)
+            .collect()
+    }
+    
+    #[test]
+    fn rms_detector_gives_smoother_gain_than_peak() {
+        // 5ms 满幅度的 1kHz 瞬态，之后是1秒 -14dBFS 的 100Hz 稳定音
+        let burst_len = (0.005 * SAMPLE_RATE) as usize;
+        let mut input = sine(1000.0, 1.0, burst_len);
+        input.extend(sine(100.0, 0.2, 44100));
+        
+        let gains = |detector: u8| {
+            let mut settings = compressor_settings(-20.0, 4.0, 0.001, 0.005);
+            settings.detector = detector;
+            let mut output = input.clone();
+            AudioProcessor::new().apply_compression_internal(&mut output, &settings);
+            
+            // 瞬态及其后10ms内的最小增益（dB），以及跳过前100ms后稳定段增益的方差
+            let split = burst_len + 441;
+            let transient = gain_trajectory(&input[..split], &output[..split]);
+            let min_gain_db = amplitude_to_db(transient.iter().copied().fold(1.0, f32::min));
+            let steady = gain_trajectory(&input[4410..], &output[4410..]);
+            let mean = steady.iter().sum::<f32>() / steady.len() as f32;
+            let variance = steady.iter().map(|g| (g - mean) * (g - mean)).sum::<f32>() / steady.len() as f32;
+            (min_gain_db, variance)
+        };
+        
+        let (peak_min_db, peak_variance) = gains(0);
+        let (rms_min_db, rms_variance) = gains(1);
+        assert!(rms_min_db > peak_min_db + 2.0, "transient gain: rms {rms_min_db} dB, peak {peak_min_db} dB");
+        assert!(rms_variance < peak_variance * 0.1, "steady gain variance: rms {rms_variance}, peak {peak_variance}");
+    }
+    
+    fn rms_db(audio: &[f32]) -> f32 {
+        amplitude_to_db(AudioProcessor::new().calculate_rms(audio))
+    }
+    
+    #[test]
+    fn agc_levels_a_ramping_input() {
+        // 4秒内电平从 -40 dB 线性（按dB）升到 -6 dB 的正弦
+        let len = (4.0 * SAMPLE_RATE) as usize;
+        let mut audio: Vec<f32> = sine(300.0, 1.0, len)
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x * 10.0f32.powf((-40.0 + 34.0 * i as f32 / len as f32) / 20.0))
+            .collect();
+        
+        AudioProcessor::new().apply_agc(&mut audio, -20.0, 30.0, 100.0);
+        
+        // 去掉首尾各200ms后，每200ms的RMS都在目标附近
+        let block = (0.2 * SAMPLE_RATE) as usize;
+        for chunk in audio[block..len - block].chunks(block) {
+            let level = rms_db(chunk);
+            assert!((level + 20.0).abs() < 1.0, "block level {level} dB");
+        }
+    }
+    
+    #[test]
+    fn comb_filter_stays_bounded_at_high_feedback() {
+        let processor = AudioProcessor::new();
+        let noise = white_noise(0.5, 88200, 29);
+        let tone: Vec<f32> = (1..=10).fold(vec![0.0; 88200], |mut acc, harmonic| {
+            for (out, x) in acc.iter_mut().zip(sine(233.3 * harmonic as f32, 0.05, 88200)) {
+                *out += x;
+            }
+            acc
+        });
+        
+        for input in [noise, tone] {
+            let mut output = input.clone();
+            processor.apply_comb_filter(&mut output, 233.3, 0.99).unwrap();
+            let input_peak = processor.calculate_peak(&input);
+            let output_peak = processor.calculate_peak(&output);
+            assert!(output.iter().all(|x| x.is_finite()));
+            assert!(output_peak <= input_peak * 1.01, "output peak {output_peak}, input peak {input_peak}");
+        }
+    }
+    
+    #[test]
+    fn low_pass_is_3db_down_at_cutoff() {
+        for cutoff in [200.0f32, 1000.0, 4000.0] {
+            let filter = IIRFilter::low_pass(cutoff, SAMPLE_RATE, 0.707);
+            let at_cutoff = amplitude_to_db(filter.response(cutoff, SAMPLE_RATE).norm());
+            let passband = amplitude_to_db(filter.response(cutoff / 20.0, SAMPLE_RATE).norm());
+            assert!((at_cutoff + 3.0).abs() < 0.1, "{cutoff} Hz: {at_cutoff} dB at cutoff");
+            assert!(passband.abs() < 0.1, "{cutoff} Hz: {passband} dB in passband");
+        }
+    }
+    
+    #[test]
+    fn quantize_without_dither_uses_at_most_2_pow_bits_levels() {
+        let processor = AudioProcessor::new();
+        let input = white_noise(1.2, 20000, 31);
+        
+        for bits in [2u32, 4, 8] {
+            let output = processor.quantize_to_bits(&input, bits, false);
+            // 换算为整数电平序号（同时检查都落在量化格点上），-0.0 与 0.0 为同一电平
+            let scale = (1u32 << (bits - 1)) as f32;
+            let mut levels: Vec<i32> = output
+                .iter()
+                .map(|&x| {
+                    let level = x * scale;
+                    assert_eq!(level, level.round());
+                    level as i32
+                })
+                .collect();
+            levels.sort_unstable();
+            levels.dedup();
+            assert!(levels.len() <= 1 << bits, "{bits} bits: {} levels", levels.len());
+            assert!(output.iter().all(|&x| (-1.0..1.0).contains(&x)));
+        }
+    }
+    
+    #[test]
+    fn hann_is_cola_at_half_and_quarter_hop() {
+        assert!(verify_cola(WindowType::Hann, 1024, 512) < 1e-3);
+        assert!(verify_cola(WindowType::Hann, 1024, 256) < 1e-3);
+    }
+    
+    #[test]
+    fn overlap_add_reconstructs_identity_frames() {
+        let window_size = 1024;
+        let input = sine(440.0, 0.5, 10000);
+        
+        for hop in [512, 256] {
+            let window = WindowType::Hann.coefficients(window_size);
+            let mut overlap_add = OverlapAdd::new(window.clone(), input.len());
+            
+            // 帧从负偏移开始，使开头的样本也被多帧覆盖
+            let mut frame_start = hop as isize - window_size as isize;
+            while frame_start < input.len() as isize {
+                let frame: Vec<f32> = (0..window_size)
+                    .map(|j| {
+                        let idx = frame_start + j as isize;
+                        let sample = if idx >= 0 && (idx as usize) < input.len() { input[idx as usize] } else { 0.0 };
+                        sample * window[j]
+                    })
+                    .collect();
+                overlap_add.add_frame(frame_start, &frame, 1.0);
+                frame_start += hop as isize;
+            }
+            
+            let output = overlap_add.finish();
+            for (i, (x, y)) in input.iter().zip(output.iter()).enumerate() {
+                assert!((x - y).abs() < 1e-4, "hop {hop} sample {i}: {x} vs {y}");
+            }
+        }
+    }
+    
+    #[test]
+    fn envelope_follower_step_response_matches_time_constants() {
+        let attack = 0.01;
+        let release = 0.1;
+        let mut follower = EnvelopeFollower::new(SAMPLE_RATE, attack, release, 0);
+        
+        // 阶跃上升：一个攻击时间常数后约为63%
+        let attack_samples = (attack * SAMPLE_RATE) as usize;
+        let mut envelope = 0.0;
+        for _ in 0..attack_samples {
+            envelope = follower.process(1.0);
+        }
+        assert!((envelope - 0.632).abs() < 0.01, "after attack time: {envelope}");
+        
+        // 充满后阶跃下降：一个释放时间常数后剩约37%
+        for _ in 0..(SAMPLE_RATE as usize) {
+            follower.process(1.0);
+        }
+        let release_samples = (release * SAMPLE_RATE) as usize;
+        for _ in 0..release_samples {
+            envelope = follower.process(0.0);
+        }
+        assert!((envelope - 0.368).abs() < 0.01, "after release time: {envelope}");
+    }
+    
+    #[test]
+    fn f32_equalizer_does_not_drift_from_f64_over_long_sweep() {
+        let processor = AudioProcessor::new();
+        let settings = EqualizerSettings { bass: 2.0, mid: 0.8, treble: 1.5 };
+        
+        // 60秒对数扫频 20Hz-20kHz
+        let duration = 60.0f64;
+        let sample_rate = SAMPLE_RATE as f64;
+        let k = (20000.0f64 / 20.0).ln();
+        let sweep: Vec<f64> = (0..(duration * sample_rate) as usize)
+            .map(|i| {
+                let t = i as f64 / sample_rate;
+                let phase = 2.0 * std::f64::consts::PI * 20.0 * duration / k * ((k * t / duration).exp() - 1.0);
+                0.5 * phase.sin()
+            })
+            .collect();
+        
+        let mut output_f32: Vec<f32> = sweep.iter().map(|&x| x as f32).collect();
+        let mut output_f64 = sweep.clone();
+        processor.apply_equalizer_internal(&mut output_f32, &settings);
+        processor.apply_equalizer_f64_internal(&mut output_f64, &settings);
+        
+        // 每10秒一段比较误差/信号能量比：应始终很小，且不随时间累积
+        let chunk = output_f64.len() / 6;
+        let errors_db: Vec<f64> = (0..6)
+            .map(|c| {
+                let range = c * chunk..(c + 1) * chunk;
+                let error: f64 = output_f32[range.clone()].iter().zip(&output_f64[range.clone()])
+                    .map(|(&a, &b)| (a as f64 - b).powi(2))
+                    .sum();
+                let signal: f64 = output_f64[range].iter().map(|b| b * b).sum();
+                10.0 * (error / signal).log10()
+            })
+            .collect();
+        
+        assert!(errors_db.iter().all(|&e| e < -70.0), "{errors_db:?}");
+        assert!(errors_db[5] <= errors_db[0], "{errors_db:?}");
+    }
+    
+    #[test]
+    fn reset_analysis_restores_fresh_analysis_state() {
+        let fresh = AudioProcessor::new();
+        let mut processor = AudioProcessor::new();
+        processor.set_crossover(300.0, 3000.0).unwrap();
+        
+        // 按 process_audio_frame 的方式喂入几帧，弄脏全部分析状态
+        let mut audio = sine(220.0, 0.9, 4096);
+        audio[4000] = 1.0;
+        for frame in audio.chunks(1024) {
+            if processor.check_overload(frame) {
+                processor.overload_hold_remaining = processor.ms_to_samples(OVERLOAD_HOLD_MS);
+            }
+            processor.envelope = processor.calculate_rms(frame);
+            processor.update_rms_history(frame);
+            processor.latest_pitch = processor.detect_pitch(frame);
+            if let Some(pitch) = processor.latest_pitch {
+                processor.pitch_history.remove(0);
+                processor.pitch_history.push(pitch);
+            }
+            let flux = processor.compute_spectral_flux(frame);
+            processor.spectral_flux_history.remove(0);
+            processor.spectral_flux_history.push(flux);
+        }
+        assert!(processor.envelope > 0.0);
+        assert!(processor.overload_hold_remaining > 0);
+        assert!(processor.latest_pitch.is_some());
+        assert!(processor.prev_spectrum.is_some());
+        assert_ne!(processor.pitch_history, fresh.pitch_history);
+        assert_ne!(processor.rms_history, fresh.rms_history);
+        
+        processor.reset_analysis();
+        
+        assert_eq!(processor.envelope, fresh.envelope);
+        assert_eq!(processor.pitch_history, fresh.pitch_history);
+        assert_eq!(processor.spectral_flux_history, fresh.spectral_flux_history);
+        assert_eq!(processor.rms_history, fresh.rms_history);
+        assert_eq!(processor.rms_accumulator, fresh.rms_accumulator);
+        assert_eq!(processor.prev_spectrum, fresh.prev_spectrum);
+        assert_eq!(processor.overload_hold_remaining, fresh.overload_hold_remaining);
+        assert_eq!(processor.latest_pitch, fresh.latest_pitch);
+        // 配置不受影响
+        assert_eq!((processor.low_mid_crossover, processor.mid_high_crossover), (300.0, 3000.0));
+    }
+    
+    #[test]
+    fn auto_makeup_preserves_integrated_loudness() {
+        let processor = AudioProcessor::new();
+        // 音量起伏的信号：0.5秒一段，响/轻交替
+        let input: Vec<f32> = sine(440.0, 1.0, 4 * SAMPLE_RATE as usize)
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| if (i / (SAMPLE_RATE as usize / 2)).is_multiple_of(2) { 0.8 * x } else { 0.1 * x })
+            .collect();
+        let input_lufs = processor.integrated_loudness(&input).unwrap();
+        
+        let mut settings = compressor_settings(-30.0, 6.0, 0.005, 0.05);
+        let mut plain = input.clone();
+        processor.apply_compression_internal(&mut plain, &settings);
+        let plain_lufs = processor.integrated_loudness(&plain).unwrap();
+        
+        settings.auto_makeup = true;
+        let mut output = input.clone();
+        processor.apply_compression_internal(&mut output, &settings);
+        let output_lufs = processor.integrated_loudness(&output).unwrap();
+        
+        assert!(input_lufs - plain_lufs > 6.0, "without makeup: {input_lufs} -> {plain_lufs}");
+        assert!((output_lufs - input_lufs).abs() < 1.0, "with auto makeup: {input_lufs} -> {output_lufs}");
+    }
+    
+    #[test]
+    fn pink_noise_falls_3db_per_octave() {
+        let noise = generate_pink_noise(10.0, SAMPLE_RATE as u32, 0.1);
+        
+        // Welch 平均功率谱
+        let n = 4096;
+        let window = WindowType::Hann.coefficients(n);
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(n);
+        let mut spectrum = r2c.make_output_vec();
+        let mut psd = vec![0.0f64; n / 2 + 1];
+        for frame in noise.chunks_exact(n) {
+            let mut buffer: Vec<f32> = frame.iter().zip(&window).map(|(x, w)| x * w).collect();
+            r2c.process(&mut buffer, &mut spectrum).unwrap();
+            psd.iter_mut().zip(&spectrum).for_each(|(p, bin)| *p += bin.norm_sqr() as f64);
+        }
+        
+        // 每个倍频程（100Hz 起）的平均功率谱密度，对 log2(f) 做线性回归
+        let bin_hz = SAMPLE_RATE as f64 / n as f64;
+        let points: Vec<(f64, f64)> = (0..7)
+            .map(|octave| {
+                let low = 100.0 * 2f64.powi(octave);
+                let bins = &psd[(low / bin_hz) as usize..(2.0 * low / bin_hz) as usize];
+                let density = bins.iter().sum::<f64>() / bins.len() as f64;
+                (octave as f64, 10.0 * density.log10())
+            })
+            .collect();
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / points.len() as f64;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / points.len() as f64;
+        let slope = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum::<f64>()
+            / points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum::<f64>();
+        
+        assert!((slope + 3.0).abs() < 0.5, "slope {slope} dB/octave, {points:?}");
+    }
+    
+    #[test]
+    fn transient_shaper_boosts_attacks_and_is_transparent_at_unity() {
+        let processor = AudioProcessor::new();
+        // 打击乐式信号：每0.25秒一次敲击，200Hz正弦按50ms时间常数衰减
+        let hit_len = SAMPLE_RATE as usize / 4;
+        let input: Vec<f32> = (0..8 * hit_len)
+            .map(|i| {
+                let t = (i % hit_len) as f32 / SAMPLE_RATE;
+                0.5 * (-t / 0.05).exp() * (2.0 * std::f32::consts::PI * 200.0 * t).sin()
+            })
+            .collect();
+        
+        let mut unity = input.clone();
+        processor.apply_transient_shaper(&mut unity, 1.0, 1.0);
+        assert_eq!(unity, input);
+        
+        let mut shaped = input.clone();
+        processor.apply_transient_shaper(&mut shaped, 2.0, 1.0);
+        
+        // 比较每次敲击起音段（前5ms）和衰减尾部（100~200ms）的电平变化，跳过第一次敲击
+        let level_change_db = |offset_ms: usize, len_ms: usize| {
+            let offset = SAMPLE_RATE as usize * offset_ms / 1000;
+            let len = SAMPLE_RATE as usize * len_ms / 1000;
+            let mut before = Vec::new();
+            let mut after = Vec::new();
+            for hit in 1..8 {
+                let start = hit * hit_len + offset;
+                before.extend_from_slice(&input[start..start + len]);
+                after.extend_from_slice(&shaped[start..start + len]);
+            }
+            rms_db(&after) - rms_db(&before)
+        };
+        let attack_change = level_change_db(0, 5);
+        let tail_change = level_change_db(100, 100);
+        
+        assert!(attack_change > 3.0, "attack {attack_change} dB");
+        assert!(tail_change < 1.0, "tail {tail_change} dB");
+    }
+    
+    #[test]
+    fn waveform_handles_zero_points_and_upsampling() {
+        let processor = AudioProcessor::new();
+        let audio = [0.1, -0.2, 0.3, -0.4];
+        
+        assert!(processor.generate_waveform(&audio, 0, None, None).is_empty());
+        assert!(processor.generate_waveform(&audio, 0, Some(WAVEFORM_MODE_BOTH), None).is_empty());
+        
+        // 点数多于样本数：按位置取最近样本
+        let peaks = processor.generate_waveform(&audio, 8, None, None);
+        assert_eq!(&*peaks, &[0.1, 0.1, 0.2, 0.2, 0.3, 0.3, 0.4, 0.4]);
+        let signed = processor.generate_waveform(&audio, 8, None, Some(true));
+        assert_eq!(&*signed, &[0.1, 0.1, -0.2, -0.2, 0.3, 0.3, -0.4, -0.4]);
+        let both = processor.generate_waveform(&audio, 6, Some(WAVEFORM_MODE_BOTH), None);
+        assert_eq!(&*both, &[0.1, 0.1, 0.1, 0.1, 0.2, 0.2, 0.3, 0.3, 0.3, 0.3, 0.4, 0.4]);
+    }
+    
+    #[test]
+    fn blackman_harris_is_normalized_with_low_sidelobes() {
+        let n = 4096;
+        let window = WindowType::BlackmanHarris.coefficients(n);
+        // 系数之和为1：中心峰值为1，两端接近0，均值为 a0
+        assert!((window[n / 2] - 1.0).abs() < 1e-6);
+        assert!(window[0].abs() < 1e-4);
+        assert!((window.iter().sum::<f32>() / n as f32 - 0.35875).abs() < 1e-5);
+        assert!((1..n / 2).all(|i| (window[i] - window[n - i]).abs() < 1e-6));
+        
+        // 频率落在两个频点之间的纯音：主瓣以外的最大旁瓣（相对峰值，dB）
+        let tone: Vec<f32> = (0..n)
+            .map(|i| (2.0 * std::f32::consts::PI * 100.5 * i as f32 / n as f32).sin())
+            .collect();
+        let max_sidelobe_db = |window_type: WindowType| {
+            let window = window_type.coefficients(n);
+            let mut planner = RealFftPlanner::<f32>::new();
+            let r2c = planner.plan_fft_forward(n);
+            let mut buffer: Vec<f32> = tone.iter().zip(&window).map(|(x, w)| x * w).collect();
+            let mut spectrum = r2c.make_output_vec();
+            r2c.process(&mut buffer, &mut spectrum).unwrap();
+            let magnitudes: Vec<f32> = spectrum.iter().map(|bin| bin.norm()).collect();
+            let peak = magnitudes.iter().copied().fold(0.0, f32::max);
+            let sidelobe = magnitudes.iter().enumerate()
+                .filter(|(k, _)| (*k as f32 - 100.5).abs() > 6.0)
+                .map(|(_, &m)| m)
+                .fold(0.0, f32::max);
+            amplitude_to_db(sidelobe / peak)
+        };
+        let hann_db = max_sidelobe_db(WindowType::Hann);
+        let blackman_harris_db = max_sidelobe_db(WindowType::BlackmanHarris);
+        
+        assert!(blackman_harris_db < -85.0, "Blackman-Harris sidelobes {blackman_harris_db} dB");
+        assert!(blackman_harris_db < hann_db - 30.0, "Hann {hann_db} dB, Blackman-Harris {blackman_harris_db} dB");
+    }
+    
+    #[test]
+    fn flat_linear_phase_eq_reproduces_input() {
+        let processor = AudioProcessor::new();
+        let input = white_noise(0.5, 20000, 13);
+        let flat = EqBandGainsDb { bass: 0.0, mid: 0.0, treble: 0.0 };
+        let output = processor.apply_linear_phase_eq_internal(&input, &flat).unwrap();
+        
+        assert_eq!(output.len(), input.len());
+        for (i, (x, y)) in input.iter().zip(output.iter()).enumerate() {
+            assert!((x - y).abs() < 1e-4, "sample {i}: {x} vs {y}");
+        }
+    }
+    
+    #[test]
+    fn pitch_window_loudest_finds_off_center_burst() {
+        let mut audio = vec![0.0f32; 4096];
+        let burst_start = 200;
+        audio[burst_start..burst_start + 1024].copy_from_slice(&sine(220.0, 0.8, 1024));
+        
+        let mut processor = AudioProcessor::new();
+        processor.set_pitch_window_loudest();
+        assert_eq!(processor.pitch_window_start(&audio, 1024), burst_start);
+        
+        processor.set_pitch_window_center();
+        assert_ne!(processor.pitch_window_start(&audio, 1024), burst_start);
+        assert_eq!(processor.pitch_window_start(&audio, 1024), (4096 - 1024) / 2);
+    }
+    
+    #[test]
+    fn disabling_all_eq_bands_is_transparent() {
+        let mut processor = AudioProcessor::new();
+        for band in 0..3 {
+            processor.set_band_enabled(band, false);
+        }
+        
+        let input = white_noise(0.5, 8192, 11);
+        let mut output = input.clone();
+        let settings = EqualizerSettings { bass: 2.0, mid: 0.5, treble: 3.0 };
+        processor.apply_equalizer_internal(&mut output, &settings);
+        assert_eq!(output, input);
+    }
+    
+    #[test]
+    fn in_place_equalizer_matches_allocating_three_pass_version() {
+        let processor = AudioProcessor::new();
+        let input = white_noise(0.5, 16384, 17);
+        let settings = EqualizerSettings { bass: 1.8, mid: 0.7, treble: 1.4 };
+        
+        // 参考实现：原先分配整段缓冲区、按中频/低频/高频三遍累加的版本
+        let (mut bass_filter, mut treble_filter) = processor.equalizer_filters();
+        let mut expected: Vec<f32> = input.iter().map(|&x| x * settings.mid).collect();
+        for (out, &x) in expected.iter_mut().zip(&input) {
+            *out += bass_filter.process(x) * (settings.bass - settings.mid);
+        }
+        for (out, &x) in expected.iter_mut().zip(&input) {
+            *out += treble_filter.process(x) * (settings.treble - settings.mid);
+        }
+        
+        let mut output = input.clone();
+        processor.apply_equalizer_internal(&mut output, &settings);
+        assert_eq!(output, expected);
+    }
+    
+    // Blackman-Harris 窗功率谱中低于 cutoff_hz 的能量相对总能量（dB）
+    fn energy_below_db(audio: &[f32], cutoff_hz: f32) -> f32 {
+        let n = audio.len();
+        let window = WindowType::BlackmanHarris.coefficients(n);
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(n);
+        let mut buffer: Vec<f32> = audio.iter().zip(&window).map(|(x, w)| x * w).collect();
+        let mut spectrum = r2c.make_output_vec();
+        r2c.process(&mut buffer, &mut spectrum).unwrap();
+        
+        let cutoff_bin = (cutoff_hz / SAMPLE_RATE * n as f32) as usize;
+        let total: f32 = spectrum.iter().map(|bin| bin.norm_sqr()).sum();
+        let below: f32 = spectrum[..cutoff_bin].iter().map(|bin| bin.norm_sqr()).sum();
+        10.0 * (below / total).log10()
+    }
+    
+    #[test]
+    fn oversampled_compressor_reduces_aliasing() {
+        let input = sine(7000.0, 0.9, 44100);
+        let alias_db = |oversample: u8| {
+            let mut settings = compressor_settings(-20.0, 10.0, 0.0001, 0.002);
+            settings.oversample = oversample;
+            let mut output = input.clone();
+            AudioProcessor::new().apply_compression_internal(&mut output, &settings);
+            energy_below_db(&output[8192..8192 + 16384], 6500.0)
+        };
+        
+        let plain = alias_db(1);
+        let oversampled = alias_db(4);
+        assert!(oversampled < plain - 15.0, "1x {plain} dB, 4x {oversampled} dB");
+    }
+    
+    #[test]
+    fn denoise_more_passes_leave_less_residual_noise() {
+        let processor = AudioProcessor::new();
+        let noise = white_noise(0.2, 88200, 7);
+        
+        let residual_db = |passes: u32| {
+            let mut audio = noise.clone();
+            processor.denoise_audio(&mut audio, 3.0, Some(passes)).unwrap();
+            rms_db(&audio[4096..84000])
+        };
+        
+        let one_pass = residual_db(1);
+        let three_passes = residual_db(3);
+        assert!(three_passes < one_pass - 10.0, "1 pass {one_pass} dB, 3 passes {three_passes} dB");
+    }
+    
+    // 大音量段结束后，增益恢复到距1.0不足1dB所需的时间（秒）
+    fn compressor_recovery_sec(settings: &CompressorSettings, loud_sec: f32) -> f32 {
+        let loud_len = (loud_sec * SAMPLE_RATE) as usize;
+        let quiet_len = (4.0 * SAMPLE_RATE) as usize;
+        let mut input = sine(440.0, 1.0, loud_len);
+        input.extend(sine(440.0, 0.01, quiet_len));
+        
+        let mut output = input.clone();
+        AudioProcessor::new().apply_compression_internal(&mut output, settings);
+        
+        let limit = db_to_amplitude(-1.0);
+        let last_reduced = (loud_len..input.len())
+            .rev()
+            .filter(|&i| input[i].abs() > 0.005)
+            .find(|&i| output[i] / input[i] < limit)
+            .unwrap_or(loud_len);
+        (last_reduced - loud_len) as f32 / SAMPLE_RATE
+    }
+    
+    #[test]
+    fn auto_release_recovers_faster_after_transient_than_after_steady_tone() {
+        let mut settings = compressor_settings(-30.0, 10.0, 0.001, 0.3);
+        settings.auto_release = true;
+        let after_burst = compressor_recovery_sec(&settings, 0.03);
+        let after_steady = compressor_recovery_sec(&settings, 2.0);
+        assert!(after_burst < 0.5, "after burst {after_burst} s");
+        assert!(after_steady > 3.0 * after_burst, "after burst {after_burst} s, after steady tone {after_steady} s");
+        
+        // 固定释放时间下两者相近
+        settings.auto_release = false;
+        let fixed_burst = compressor_recovery_sec(&settings, 0.03);
+        let fixed_steady = compressor_recovery_sec(&settings, 2.0);
+        assert!((fixed_steady - fixed_burst).abs() < 0.3 * fixed_steady, "fixed release: {fixed_burst} s vs {fixed_steady} s");
+    }
+    
+    // 各频点逐帧增益 |Y|/|X| 的相邻帧差的均方（在有能量的频点上平均）
+    fn frame_to_frame_gain_variance(input: &[f32], output: &[f32]) -> f32 {
+        let (n, hop) = (2048, 512);
+        let window = WindowType::Hann.coefficients(n);
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(n);
+        let magnitudes = |audio: &[f32], start: usize| {
+            let mut buffer: Vec<f32> = audio[start..start + n].iter().zip(&window).map(|(x, w)| x * w).collect();
+            let mut spectrum = r2c.make_output_vec();
+            r2c.process(&mut buffer, &mut spectrum).unwrap();
+            spectrum.iter().map(|bin| bin.norm()).collect::<Vec<f32>>()
+        };
+        
+        let gains: Vec<Vec<f32>> = (n..input.len() - 2 * n)
+            .step_by(hop)
+            .map(|start| {
+                let before = magnitudes(input, start);
+                let after = magnitudes(output, start);
+                before.iter().zip(&after).map(|(b, a)| if *b > 1e-6 { a / b } else { 0.0 }).collect()
+            })
+            .collect();
+        let diffs: Vec<f32> = gains.windows(2)
+            .flat_map(|pair| pair[0].iter().zip(&pair[1]).map(|(a, b)| (b - a).powi(2)).collect::<Vec<_>>())
+            .collect();
+        diffs.iter().sum::<f32>() / diffs.len() as f32
+    }
+    
+    #[test]
+    fn denoise_smoothing_reduces_gain_fluctuation() {
+        let input: Vec<f32> = sine(440.0, 0.3, 88200).iter()
+            .zip(white_noise(0.1, 88200, 11))
+            .map(|(s, n)| s + n)
+            .collect();
+        let variance = |smoothing: f32| {
+            let mut processor = AudioProcessor::new();
+            processor.set_denoise_smoothing(smoothing);
+            let mut output = input.clone();
+            processor.denoise_audio(&mut output, 2.0, None).unwrap();
+            frame_to_frame_gain_variance(&input, &output)
+        };
+        let unsmoothed = variance(0.0);
+        let smoothed = variance(0.9);
+        
+        assert!(smoothed < unsmoothed / 4.0, "unsmoothed {unsmoothed}, smoothed {smoothed}");
+    }
+    
+    #[test]
+    fn g711_matches_reference_values() {
+        assert_eq!(ulaw_to_linear(0xFF), 0);
+        assert_eq!(ulaw_to_linear(0x00), -32124);
+        assert_eq!(ulaw_to_linear(0x80), 32124);
+        assert_eq!(linear_to_ulaw(0), 0xFF);
+        assert_eq!(linear_to_ulaw(i16::MAX), 0x80);
+        assert_eq!(linear_to_ulaw(i16::MIN), 0x00);
+        
+        assert_eq!(alaw_to_linear(0xD5), 8);
+        assert_eq!(alaw_to_linear(0x55), -8);
+        assert_eq!(alaw_to_linear(0xAA), 32256);
+        assert_eq!(linear_to_alaw(0), 0xD5);
+        assert_eq!(linear_to_alaw(i16::MAX), 0xAA);
+    }
+    
+    #[test]
+    fn g711_round_trips_within_quantization_error() {
+        // 每个码字解码后再编码得到同一码字（μ-law 的负零 0x7F 编码为正零 0xFF）
+        for byte in 0..=255u8 {
+            let ulaw = linear_to_ulaw(ulaw_to_linear(byte));
+            assert_eq!(ulaw, if byte == 0x7F { 0xFF } else { byte }, "μ-law {byte:#04x}");
+            assert_eq!(linear_to_alaw(alaw_to_linear(byte)), byte, "A-law {byte:#04x}");
+        }
+        
+        // 浮点采样往返：对数量化误差不超过幅度的 1/32 加上最小量化步长
+        let processor = AudioProcessor::new();
+        let input: Vec<f32> = (-10000..=10000).map(|i| i as f32 / 10000.0).collect();
+        let ulaw = processor.decode_ulaw(&processor.encode_ulaw(&input));
+        let alaw = processor.decode_alaw(&processor.encode_alaw(&input));
+        for ((&x, &u), &a) in input.iter().zip(ulaw.iter()).zip(alaw.iter()) {
+            let tolerance = x.abs() / 32.0 + 1.0 / 2048.0;
+            assert!((u - x).abs() <= tolerance, "μ-law {x} -> {u}");
+            assert!((a - x).abs() <= tolerance, "A-law {x} -> {a}");
+        }
+    }
+    
+    #[test]
+    fn dereverberate_shortens_reverb_tail() {
+        // “语音”：150Hz 基频加谐波的浊音段，响0.2秒、停0.4秒
+        let period = (0.6 * SAMPLE_RATE) as usize;
+        let burst = (0.2 * SAMPLE_RATE) as usize;
+        let dry: Vec<f32> = (0..5 * period)
+            .map(|i| {
+                if i % period >= burst {
+                    return 0.0;
+                }
+                let t = i as f32 / SAMPLE_RATE;
+                (1..=6).map(|h| (2.0 * std::f32::consts::PI * 150.0 * h as f32 * t).sin() / h as f32).sum::<f32>() * 0.2
+            })
+            .collect();
+        
+        // 合成房间冲激响应：直达声加 RT60 = 0.6 秒的指数衰减白噪声
+        let rir_len = (0.6 * SAMPLE_RATE) as usize;
+        let mut rir: Vec<f32> = white_noise(0.05, rir_len, 3).iter()
+            .enumerate()
+            .map(|(i, &n)| n * (-6.9 * i as f32 / rir_len as f32).exp())
+            .collect();
+        rir[0] = 1.0;
+        let mut reverberant = dry.clone();
+        fir_filter(&mut reverberant, &rir).unwrap();
+        
+        let mut processed = reverberant.clone();
+        AudioProcessor::new().dereverberate(&mut processed, 1.0).unwrap();
+        
+        // 跳过第一段；尾音取静音段开始后50~350ms，浊音段取50~200ms
+        let level_change_db = |offset: f32, len: f32| {
+            let mut before = Vec::new();
+            let mut after = Vec::new();
+            for segment in 1..5 {
+                let start = segment * period + (offset * SAMPLE_RATE) as usize;
+                let end = start + (len * SAMPLE_RATE) as usize;
+                before.extend_from_slice(&reverberant[start..end]);
+                after.extend_from_slice(&processed[start..end]);
+            }
+            rms_db(&after) - rms_db(&before)
+        };
+        let tail_change = level_change_db(0.25, 0.3);
+        let burst_change = level_change_db(0.05, 0.15);
+        
+        assert!(tail_change < -3.0, "tail {tail_change} dB");
+        assert!(burst_change > -1.5, "burst {burst_change} dB");
+    }
+}